@@ -1,16 +1,33 @@
+pub mod canvas;
 pub mod client;
+pub mod constraint;
+pub mod cursor;
+pub mod damage;
+pub mod data_device;
 pub mod dispatch;
 pub mod error;
+pub mod event;
+pub mod gesture;
+pub mod keyboard;
+pub mod output;
+pub mod pointer;
+pub mod primary_selection;
+pub mod seat;
+pub mod shm;
+pub mod tablet;
+pub mod timing;
+pub mod touch;
+
+#[cfg(test)]
+mod testing;
 
-use std::io::Seek;
-use std::io::SeekFrom;
-use std::io::Write;
 use std::process;
 
 pub use client::Client;
 pub use client::State;
 pub use client::Window;
 pub use error::ClientError;
+pub use event::Event;
 
 const DEFAULT_PIXEL_FORMAT: wayland_client::protocol::wl_shm::Format =
     wayland_client::protocol::wl_shm::Format::Argb8888;
@@ -35,7 +52,6 @@ fn main() {
     let _ = client.create_window("woah", "app").unwrap();
     let _ = client.create_window("woah", "app").unwrap();
 
-    let mut buff: Vec<u8> = vec![0; 100 * 100];
     loop {
         match client.dispatch() {
             Ok(_) => (),
@@ -44,36 +60,22 @@ fn main() {
             }
         }
 
-        for (idx, window) in client.globals.windows.iter_mut().enumerate() {
-            let (width, height) = (window.width, window.height);
-            let pixel_size = client::bytes_per_pixel(DEFAULT_PIXEL_FORMAT).unwrap();
-            let stride = width * pixel_size;
-            let size = (stride * height) as usize;
+        while let Some(event) = client.poll_event() {
+            if let Event::FrameReady { window, .. } = event
+                && let Some(fps) = client.globals.windows[window].frame_stats().fps()
+            {
+                println!("* Window ({window}) running at {fps:.1} fps");
+            }
+        }
 
-            buff.resize(size, 0);
+        for (idx, window) in client.globals.windows.iter_mut().enumerate() {
+            if !window.should_redraw() {
+                continue;
+            }
 
-            // IMPORTANT: think about how buffers and files work (I'm confused a little)
-            if let Some(buffer) = window.buffers.iter_mut().find(|b| !b.destroy && !b.used) {
-                for chunk in buff.chunks_mut(pixel_size as usize) {
-                    if idx % 2 == 0 {
-                        chunk[0] = 200;
-                        chunk[1] = 20;
-                        chunk[2] = 200;
-                        chunk[3] = 255;
-                    } else {
-                        chunk[0] = 90;
-                        chunk[1] = 90;
-                        chunk[2] = 255;
-                        chunk[3] = 255;
-                    }
-                }
-                // TODO: you can use offset to determine  start point (buffer.offset)
-                // IMPORTANT: check if you need to use seek from start
-                window.file.seek(SeekFrom::Start(buffer.offset)).unwrap();
-                // IMPORTANT: read docs for write_all and compare it to just write
-                window.file.write_all(buff.as_slice()).unwrap();
-                // STUDY: read docs for that foo
-                // window.file.sync_all().unwrap()
+            let color = if idx % 2 == 0 { 0xFFC814C8 } else { 0xFFFF5A5A };
+            if let Err(err) = window.draw(|canvas| canvas.fill(color)) {
+                eprintln!("! ({idx}) failed to draw: {err}");
             }
         }
     }
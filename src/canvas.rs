@@ -0,0 +1,66 @@
+use super::client::bytes_per_pixel;
+use super::damage::Rect;
+use wayland_client::protocol::wl_shm;
+
+/// Writable view of a single buffer handed out by `Window::draw`. `data` is the buffer's own
+/// shared memory: it starts out holding the previous contents, and whatever is left in it when
+/// the closure returns is what gets presented.
+#[derive(Debug)]
+pub struct Canvas<'a> {
+    pub data: &'a mut [u8],
+
+    pub width: i32,
+    pub height: i32,
+    pub stride: i32,
+    pub format: wl_shm::Format,
+
+    /// How many frames old the contents are: 0 means undefined contents, 1 means the buffer holds
+    /// the previously presented frame, 2 the one before that and so on.
+    pub age: u32,
+    /// Regions that changed on screen since this buffer was last presented. Redrawing these
+    /// brings the buffer up to date with the previous frame.
+    pub stale: Vec<Rect>,
+    /// Regions changed by this draw, in buffer coordinates. Left empty, the whole buffer is
+    /// damaged.
+    pub damage: Vec<Rect>,
+}
+
+impl Canvas<'_> {
+    pub fn bounds(&self) -> Rect {
+        Rect::new(0, 0, self.width, self.height)
+    }
+
+    /// Marks a region as changed by this draw. Rectangles are clipped and merged on commit.
+    pub fn add_damage(&mut self, rect: Rect) {
+        self.damage.push(rect);
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [u8] {
+        self.data
+    }
+
+    pub fn bytes_per_pixel(&self) -> usize {
+        // `draw` only hands out canvases in formats it knows the size of
+        bytes_per_pixel(self.format).unwrap_or(4) as usize
+    }
+
+    /// Fills the whole canvas with one `0xAARRGGBB` color.
+    pub fn fill(&mut self, color: u32) {
+        let bytes = color.to_le_bytes();
+        let pixel_size = self.bytes_per_pixel();
+        for chunk in self.data.chunks_exact_mut(pixel_size) {
+            chunk.copy_from_slice(&bytes[..pixel_size]);
+        }
+    }
+
+    /// Sets one `0xAARRGGBB` pixel, ignoring coordinates outside the canvas.
+    pub fn set_pixel(&mut self, x: i32, y: i32, color: u32) {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return;
+        }
+
+        let pixel_size = self.bytes_per_pixel();
+        let start = (y * self.stride) as usize + x as usize * pixel_size;
+        self.data[start..start + pixel_size].copy_from_slice(&color.to_le_bytes()[..pixel_size]);
+    }
+}
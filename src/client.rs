@@ -1,13 +1,57 @@
+use super::canvas::Canvas;
+use super::constraint::{
+    AppliedConstraint, ConstraintData, ConstraintKind, ConstraintObject, PointerConstraint,
+};
+use super::cursor::{CursorIcon, CursorRole, CursorState, CursorThemes, CustomCursor};
+use super::damage::{self, Rect};
+use super::data_device::{
+    DataRead, DataSource, DataWrite, DragOffer, DragSource, negotiate_mime_type,
+};
 use super::error::{ClientError, ClientErrorKind};
+use super::event::Event;
+use super::gesture::{GestureEvent, Gestures};
+use super::keyboard::Keyboard;
+use super::output::Output;
+use super::pointer::{Pointer, PointerEvent};
+use super::primary_selection::PrimarySelectionSource;
+use super::seat::Seat;
+use super::shm::ShmMapping;
+use super::tablet::{Tablet, TabletPad, TabletTool};
+use super::timing::{FrameStats, PresentationInfo};
+use super::touch::Touch;
+use rustix::event::{PollFd, PollFlags, Timespec, poll};
+use std::collections::{HashMap, VecDeque};
 use std::fs::File;
 use std::io::Seek;
 use std::os::fd::{AsFd, BorrowedFd};
+use std::time::{Duration, Instant};
+use wayland_client::backend::WaylandError;
 use wayland_client::protocol::wl_callback;
 use wayland_client::{
-    Connection, EventQueue, QueueHandle,
-    protocol::{wl_buffer, wl_compositor, wl_display, wl_shm, wl_shm_pool, wl_surface},
+    Connection, DispatchError, EventQueue, Proxy, QueueHandle,
+    protocol::{
+        wl_buffer, wl_compositor,
+        wl_data_device_manager::{self, DndAction},
+        wl_display, wl_seat, wl_shm, wl_shm_pool, wl_surface,
+    },
 };
+use wayland_protocols::wp::cursor_shape::v1::client::wp_cursor_shape_manager_v1;
+use wayland_protocols::wp::fractional_scale::v1::client::{
+    wp_fractional_scale_manager_v1, wp_fractional_scale_v1,
+};
+use wayland_protocols::wp::pointer_constraints::zv1::client::zwp_pointer_constraints_v1;
+use wayland_protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gestures_v1;
+use wayland_protocols::wp::presentation_time::client::wp_presentation;
+use wayland_protocols::wp::primary_selection::zv1::client::zwp_primary_selection_device_manager_v1;
+use wayland_protocols::wp::relative_pointer::zv1::client::zwp_relative_pointer_manager_v1;
+use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_manager_v2;
+use wayland_protocols::wp::viewporter::client::{wp_viewport, wp_viewporter};
 use wayland_protocols::xdg::shell::client::{xdg_surface, xdg_toplevel, xdg_wm_base};
+use wayland_protocols::xdg::xdg_output::zv1::client::zxdg_output_manager_v1;
+
+/// How many frames of damage are remembered for buffer age tracking. Older buffers are treated as
+/// having undefined contents.
+const MAX_BUFFER_AGE: usize = 4;
 
 #[derive(Debug)]
 pub struct Client {
@@ -16,13 +60,57 @@ pub struct Client {
     pub queue: EventQueue<State>,
     pub globals: State,
 }
+/// Decides when windows are asked to redraw.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ControlFlow {
+    /// Every frame callback asks the window for a new frame.
+    #[default]
+    Continuous,
+    /// Windows are only asked to redraw after `Window::request_redraw`.
+    OnDemand,
+}
+
 #[derive(Debug)]
 pub struct State {
+    pub control_flow: ControlFlow,
+
     pub compositor: Option<wl_compositor::WlCompositor>,
     pub xdg_wm_base: Option<xdg_wm_base::XdgWmBase>,
     pub shm: Option<wl_shm::WlShm>,
+    pub presentation: Option<wp_presentation::WpPresentation>,
+    /// `clockid_t` used for presentation timestamps, announced once `wp_presentation` is bound.
+    pub presentation_clock: Option<u32>,
+
+    pub xdg_output_manager: Option<zxdg_output_manager_v1::ZxdgOutputManagerV1>,
+    pub fractional_scale_manager:
+        Option<wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1>,
+    pub viewporter: Option<wp_viewporter::WpViewporter>,
+    pub cursor_shape_manager: Option<wp_cursor_shape_manager_v1::WpCursorShapeManagerV1>,
+    pub pointer_gestures: Option<zwp_pointer_gestures_v1::ZwpPointerGesturesV1>,
+    pub pointer_constraints: Option<zwp_pointer_constraints_v1::ZwpPointerConstraintsV1>,
+    pub relative_pointer_manager:
+        Option<zwp_relative_pointer_manager_v1::ZwpRelativePointerManagerV1>,
+    pub tablet_manager: Option<zwp_tablet_manager_v2::ZwpTabletManagerV2>,
+    pub data_device_manager: Option<wl_data_device_manager::WlDataDeviceManager>,
+    pub primary_selection_manager:
+        Option<zwp_primary_selection_device_manager_v1::ZwpPrimarySelectionDeviceManagerV1>,
 
+    pub outputs: Vec<Output>,
+    pub seats: Vec<Seat>,
+    pub cursor_themes: CursorThemes,
+    pub custom_cursors: Vec<CustomCursor>,
+    /// Data put on the clipboard with `Client::set_clipboard`, until another client replaces it.
+    pub clipboard_source: Option<DataSource>,
+    /// Data selected with `Client::set_primary_selection`, until another client replaces it.
+    pub primary_selection_source: Option<PrimarySelectionSource>,
+    /// Drag started with `Client::start_drag`, until it is dropped or cancelled.
+    pub drag_source: Option<DragSource>,
+    /// Clipboard, primary selection and drag data still being written to the clients that asked.
+    pub data_writes: Vec<DataWrite>,
     pub windows: Vec<Window>,
+    pub events: VecDeque<Event>,
+    /// First error hit while handling events, returned by the next `Client::dispatch`.
+    pub error: Option<ClientError>,
 }
 #[derive(Debug)]
 pub struct Window {
@@ -32,14 +120,55 @@ pub struct Window {
 
     pub pool: wl_shm_pool::WlShmPool,
     pub file: File,
+    /// The pool file mapped into this process, `draw` writes through it.
+    pub mapping: ShmMapping,
     pub buffers: Vec<Buffer>,
 
-    pub frame: wl_callback::WlCallback,
+    /// Frame callback requested with the last commit, `None` once the compositor has fired it.
+    pub frame: Option<wl_callback::WlCallback>,
+    pub redraw_requested: bool,
 
+    pub idx: usize,
+    pub qhandle: QueueHandle<State>,
+
+    /// Logical size, as configured by the compositor.
     pub width: i32,
     pub height: i32,
+    /// Integer factor between buffer pixels and logical pixels.
+    pub scale: i32,
+    /// Scale from `wl_surface.preferred_buffer_scale`, takes precedence over output scales.
+    pub preferred_scale: Option<i32>,
+    /// Fractional scale in use, in 120ths. When set, `scale` is 1 and the viewport maps the buffer
+    /// back to the logical size.
+    pub fractional_scale: Option<u32>,
+    /// Last `wp_fractional_scale_v1.preferred_scale`, in 120ths.
+    pub preferred_fractional_scale: Option<u32>,
+    pub wp_fractional_scale: Option<wp_fractional_scale_v1::WpFractionalScaleV1>,
+    pub viewport: Option<wp_viewport::WpViewport>,
 
     pub needs_resizing: bool,
+    pub destroyed: bool,
+
+    /// Protocol objects the window created and destroyed so far.
+    pub objects: ObjectStats,
+
+    /// Number of frames committed through `draw`.
+    pub frame_count: u64,
+    /// Damage of the most recently committed frames, newest first.
+    pub damage_history: VecDeque<Vec<Rect>>,
+    pub frame_stats: FrameStats,
+
+    /// Shared `wp_presentation` global, when the compositor supports it.
+    pub presentation: Option<wp_presentation::WpPresentation>,
+    pub last_presented: Option<PresentationInfo>,
+
+    /// Ids of the outputs (`Output::id`) the surface currently overlaps.
+    pub outputs: Vec<u32>,
+
+    /// Cursor shown while the pointer is over the window.
+    pub cursor: CursorIcon,
+    /// Pointer lock or confinement asked for through `lock_pointer`/`confine_pointer`.
+    pub pointer_constraint: Option<PointerConstraint>,
 }
 
 // NOTE: to future me: maybe try creating struct like frame and hold every frame related data that
@@ -55,8 +184,84 @@ pub struct Buffer {
 
     pub width: i32,
     pub height: i32,
+
+    /// `Window::frame_count` of the frame last drawn into this buffer, 0 if never drawn.
+    pub frame: u64,
+}
+
+/// Identifies the commit a `wp_presentation_feedback` belongs to.
+#[derive(Debug, Clone, Copy)]
+pub struct FeedbackData {
+    pub window: usize,
+    pub frame: u64,
+}
+
+/// Number of protocol objects of one interface created and destroyed so far.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectCount {
+    pub created: usize,
+    pub destroyed: usize,
+}
+
+impl ObjectCount {
+    pub fn live(&self) -> usize {
+        self.created - self.destroyed
+    }
+
+    fn add(&mut self, other: ObjectCount) {
+        self.created += other.created;
+        self.destroyed += other.destroyed;
+    }
+}
+
+/// Protocol objects owned by windows and custom cursors, to check that teardown leaks nothing.
+/// Objects the compositor destroys on its own, such as frame callbacks, aren't counted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectStats {
+    pub buffers: ObjectCount,
+    pub pools: ObjectCount,
+    pub surfaces: ObjectCount,
+    pub xdg_surfaces: ObjectCount,
+    pub xdg_toplevels: ObjectCount,
+    pub viewports: ObjectCount,
+    pub fractional_scales: ObjectCount,
+}
+
+impl ObjectStats {
+    pub fn live_buffers(&self) -> usize {
+        self.buffers.live()
+    }
+
+    /// Objects created and not destroyed yet, over every interface.
+    pub fn live(&self) -> usize {
+        self.buffers.live()
+            + self.pools.live()
+            + self.surfaces.live()
+            + self.xdg_surfaces.live()
+            + self.xdg_toplevels.live()
+            + self.viewports.live()
+            + self.fractional_scales.live()
+    }
+
+    pub fn add(&mut self, other: &ObjectStats) {
+        self.buffers.add(other.buffers);
+        self.pools.add(other.pools);
+        self.surfaces.add(other.surfaces);
+        self.xdg_surfaces.add(other.xdg_surfaces);
+        self.xdg_toplevels.add(other.xdg_toplevels);
+        self.viewports.add(other.viewports);
+        self.fractional_scales.add(other.fractional_scales);
+    }
+}
+
+impl Buffer {
+    /// Sends `wl_buffer.destroy`, consuming the buffer so it can't be destroyed twice.
+    pub fn destroy(self) {
+        self.data.destroy();
+    }
 }
 
+#[allow(clippy::result_unit_err)]
 pub fn bytes_per_pixel(fmt: wl_shm::Format) -> Result<i32, ()> {
     match fmt {
         wl_shm::Format::Argb8888
@@ -84,14 +289,14 @@ impl Client {
 
         display.get_registry(&qhandle, ());
 
-        let mut globals = State {
-            compositor: None,
-            xdg_wm_base: None,
-            shm: None,
-            windows: Vec::new(),
-        };
+        let mut globals = State::new();
 
         queue.roundtrip(&mut globals)?;
+        // second roundtrip delivers the initial state of the globals bound in the first one
+        queue.roundtrip(&mut globals)?;
+        if let Some(err) = globals.error.take() {
+            return Err(err);
+        }
 
         let client = Client {
             connection,
@@ -103,9 +308,46 @@ impl Client {
         Ok(client)
     }
 
+    /// Blocks until events arrive or a client-side timer (such as key repeat) fires, then
+    /// dispatches them.
     pub fn dispatch(&mut self) -> Result<(), ClientError> {
-        self.queue.blocking_dispatch(&mut self.globals)?;
-        Ok(())
+        let qhandle = self.queue.handle();
+        // pick up cursor and constraint changes made by the application since the last dispatch
+        self.globals
+            .update_cursor(&self.connection, &qhandle, Instant::now());
+        self.globals.update_constraints(&qhandle);
+
+        let deadline = self.globals.next_deadline();
+        if deadline.is_none() && self.globals.data_writes.is_empty() {
+            self.queue.blocking_dispatch(&mut self.globals)?;
+        } else {
+            self.queue.dispatch_pending(&mut self.globals)?;
+            self.queue.flush().map_err(DispatchError::Backend)?;
+
+            if let Some(guard) = self.queue.prepare_read() {
+                let timeout =
+                    deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+                if wait_readable(guard.connection_fd(), &self.globals.data_writes, timeout)? {
+                    match guard.read() {
+                        Ok(_) => {}
+                        Err(WaylandError::Io(err))
+                            if err.kind() == std::io::ErrorKind::WouldBlock => {}
+                        Err(err) => return Err(DispatchError::Backend(err).into()),
+                    }
+                }
+            }
+
+            self.queue.dispatch_pending(&mut self.globals)?;
+        }
+
+        self.globals.write_data();
+        let now = Instant::now();
+        self.globals.fire_timers(now);
+        self.globals.update_cursor(&self.connection, &qhandle, now);
+        match self.globals.error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
     }
 
     pub fn create_window(&mut self, title: &str, id: &str) -> Result<usize, ClientError> {
@@ -114,6 +356,18 @@ impl Client {
         let xdg_surface = State::create_xdg_surface(&self.globals, &surface, &qhandle)?;
         let xdg_toplevel = xdg_surface.get_toplevel(&qhandle, self.globals.windows.len());
 
+        // fractional scales are only usable together with a viewport
+        let (wp_fractional_scale, viewport) = match (
+            &self.globals.fractional_scale_manager,
+            &self.globals.viewporter,
+        ) {
+            (Some(manager), Some(viewporter)) => (
+                Some(manager.get_fractional_scale(&surface, &qhandle, self.globals.windows.len())),
+                Some(viewporter.get_viewport(&surface, &qhandle, ())),
+            ),
+            _ => (None, None),
+        };
+
         xdg_toplevel.set_title(title.to_string());
         xdg_toplevel.set_app_id(id.to_string());
 
@@ -135,6 +389,7 @@ impl Client {
 
         let file = tempfile::tempfile()?;
         file.set_len((size) as u64)?; // TODO: add * 2 for double buffering / or remove
+        let mapping = ShmMapping::new(&file, size as usize)?;
 
         let pool = State::create_pool(&self.globals, &qhandle, &file, size)?;
 
@@ -154,8 +409,9 @@ impl Client {
             offset: 0,
             used: false,
             destroy: false,
-            width: width,
-            height: height,
+            width,
+            height,
+            frame: 0,
         };
 
         // TODO: check if you have to do that here
@@ -165,7 +421,21 @@ impl Client {
         surface.commit();
         buffer.used = true;
 
-        let frame = surface.frame(&qhandle, self.globals.windows.len());
+        let idx = self.globals.windows.len();
+        let frame = surface.frame(&qhandle, idx);
+
+        let mut objects = ObjectStats::default();
+        objects.surfaces.created += 1;
+        objects.xdg_surfaces.created += 1;
+        objects.xdg_toplevels.created += 1;
+        objects.pools.created += 1;
+        objects.buffers.created += 1;
+        if viewport.is_some() {
+            objects.viewports.created += 1;
+        }
+        if wp_fractional_scale.is_some() {
+            objects.fractional_scales.created += 1;
+        }
 
         let window = Window {
             surface,
@@ -173,169 +443,1882 @@ impl Client {
             xdg_toplevel,
             pool,
             file,
-            width: width,
-            height: height,
-            frame,
+            mapping,
+            width,
+            height,
+            scale: 1,
+            preferred_scale: None,
+            fractional_scale: None,
+            preferred_fractional_scale: None,
+            wp_fractional_scale,
+            viewport,
+            frame: Some(frame),
+            redraw_requested: true,
+            idx,
+            qhandle,
             buffers: vec![buffer],
             needs_resizing: false,
+            destroyed: false,
+            objects,
+            frame_count: 0,
+            damage_history: VecDeque::new(),
+            frame_stats: FrameStats::default(),
+            presentation: self.globals.presentation.clone(),
+            last_presented: None,
+            outputs: Vec::new(),
+            cursor: CursorIcon::default(),
+            pointer_constraint: None,
         };
 
         self.globals.windows.push(window);
 
         Ok(self.globals.windows.len() - 1)
     }
-}
 
-impl State {
-    pub fn dispatch() {
-        todo!()
+    /// Takes the oldest event produced by `dispatch`.
+    pub fn poll_event(&mut self) -> Option<Event> {
+        self.globals.events.pop_front()
     }
 
-    pub fn resize_buffer(
-        &mut self,
-        qhandle: &QueueHandle<State>,
-        idx: usize,
-    ) -> Result<(), ClientError> {
-        let pixel_format = super::DEFAULT_PIXEL_FORMAT;
-        let pixel_size = match bytes_per_pixel(pixel_format) {
-            Ok(bytes) => bytes,
-            Err(_) => {
-                return Err(ClientError::Initialization {
-                    kind: ClientErrorKind::Pixel,
-                    message: "Pixel format not found".to_string(),
-                });
-            }
+    /// Monitors that finished announcing their properties.
+    pub fn outputs(&self) -> impl Iterator<Item = &Output> {
+        self.globals.outputs.iter().filter(|output| output.ready)
+    }
+
+    pub fn output(&self, id: u32) -> Option<&Output> {
+        self.outputs().find(|output| output.id == id)
+    }
+
+    /// Input seats, `Seat::id` is the `seat` of input events.
+    pub fn seats(&self) -> &[Seat] {
+        &self.globals.seats
+    }
+
+    pub fn seat(&self, id: u32) -> Option<&Seat> {
+        self.globals.seats.iter().find(|seat| seat.id == id)
+    }
+
+    /// Puts `data` on the clipboard, keyed by MIME type (e.g. `text/plain;charset=utf-8`). Other
+    /// clients read it from this process, so the data is kept until the clipboard is replaced.
+    pub fn set_clipboard(&mut self, data: HashMap<String, Vec<u8>>) -> Result<(), ClientError> {
+        let qhandle = self.queue.handle();
+        let (Some(manager), Some(idx)) = (
+            &self.globals.data_device_manager,
+            self.globals.clipboard_seat(),
+        ) else {
+            return Err(ClientError::Initialization {
+                kind: ClientErrorKind::Clipboard,
+                message: "Failed to set clipboard (no data device available)".to_string(),
+            });
         };
+        let seat = &self.globals.seats[idx];
+        let Some(device) = &seat.data_device else {
+            return Err(ClientError::Initialization {
+                kind: ClientErrorKind::Clipboard,
+                message: "Failed to set clipboard (no data device available)".to_string(),
+            });
+        };
+
+        let wl_data_source = manager.create_data_source(&qhandle, ());
+        for mime_type in data.keys() {
+            wl_data_source.offer(mime_type.clone());
+        }
+        device
+            .wl_data_device
+            .set_selection(Some(&wl_data_source), seat.input_serial());
+
+        if let Some(old) = self.globals.clipboard_source.replace(DataSource {
+            wl_data_source,
+            data,
+        }) {
+            old.wl_data_source.destroy();
+        }
+        self.connection.flush().map_err(DispatchError::Backend)?;
+        Ok(())
+    }
+
+    /// MIME types the current clipboard contents can be read as.
+    pub fn clipboard_mime_types(&self) -> &[String] {
+        self.globals
+            .clipboard_seat()
+            .and_then(|idx| self.globals.seats[idx].data_device.as_ref())
+            .and_then(|device| device.selection.as_ref())
+            .map(|offer| offer.mime_types.as_slice())
+            .unwrap_or(&[])
+    }
 
-        let (window_width, window_height) = {
-            let window = self.windows.get(idx).unwrap();
-            (window.width, window.height)
+    /// Starts reading the clipboard as `mime_type`. Text types fall back to an equivalent one the
+    /// source offers. The returned `DataRead` is polled without blocking the event loop, keep
+    /// dispatching while it is read since the source may be this very client.
+    pub fn read_clipboard(&self, mime_type: &str) -> Result<DataRead, ClientError> {
+        let Some(offer) = self
+            .globals
+            .clipboard_seat()
+            .and_then(|idx| self.globals.seats[idx].data_device.as_ref())
+            .and_then(|device| device.selection.as_ref())
+        else {
+            return Err(ClientError::Initialization {
+                kind: ClientErrorKind::Clipboard,
+                message: "Clipboard is empty".to_string(),
+            });
+        };
+        let Some(negotiated) = negotiate_mime_type(&offer.mime_types, mime_type) else {
+            return Err(ClientError::Initialization {
+                kind: ClientErrorKind::Clipboard,
+                message: format!("Clipboard has no {mime_type} data"),
+            });
         };
 
-        let stride = window_width * pixel_size;
-        let buffer_size = stride * window_height;
-        let total_size = buffer_size * 2;
+        let read = offer
+            .receive(negotiated)
+            .map_err(|err| ClientError::Initialization {
+                kind: ClientErrorKind::Clipboard,
+                message: format!("Failed to read clipboard: {err}"),
+            })?;
+        self.connection.flush().map_err(DispatchError::Backend)?;
+        Ok(read)
+    }
 
-        let mut file = tempfile::tempfile()?;
-        file.set_len((total_size) as u64)?;
-        file.rewind()?;
-        self.windows.get_mut(idx).unwrap().file = file;
+    /// Makes `data` the primary selection, keyed by MIME type like `set_clipboard`. Call it when
+    /// text gets selected, other clients usually paste it on a middle click.
+    pub fn set_primary_selection(
+        &mut self,
+        data: HashMap<String, Vec<u8>>,
+    ) -> Result<(), ClientError> {
+        let qhandle = self.queue.handle();
+        let (Some(manager), Some(idx)) = (
+            &self.globals.primary_selection_manager,
+            self.globals.primary_selection_seat(),
+        ) else {
+            return Err(ClientError::Initialization {
+                kind: ClientErrorKind::Clipboard,
+                message: "Failed to set primary selection (no primary selection device available)"
+                    .to_string(),
+            });
+        };
+        let seat = &self.globals.seats[idx];
+        let Some(device) = &seat.primary_selection else {
+            return Err(ClientError::Initialization {
+                kind: ClientErrorKind::Clipboard,
+                message: "Failed to set primary selection (no primary selection device available)"
+                    .to_string(),
+            });
+        };
 
-        let mut pool = Self::create_pool(
-            &self,
-            &qhandle,
-            &self.windows.get(idx).unwrap().file,
-            total_size,
-        )?;
+        let zwp_primary_selection_source = manager.create_source(&qhandle, ());
+        for mime_type in data.keys() {
+            zwp_primary_selection_source.offer(mime_type.clone());
+        }
+        device
+            .zwp_primary_selection_device
+            .set_selection(Some(&zwp_primary_selection_source), seat.input_serial());
 
-        let buffer0 = pool.create_buffer(
-            0,
-            window_width,
-            window_height,
-            stride,
-            pixel_format,
-            &qhandle,
-            idx,
-        );
+        if let Some(old) = self
+            .globals
+            .primary_selection_source
+            .replace(PrimarySelectionSource {
+                zwp_primary_selection_source,
+                data,
+            })
+        {
+            old.zwp_primary_selection_source.destroy();
+        }
+        self.connection.flush().map_err(DispatchError::Backend)?;
+        Ok(())
+    }
 
-        let buffer1 = pool.create_buffer(
-            buffer_size,
-            window_width,
-            window_height,
-            stride,
-            pixel_format,
-            &qhandle,
-            idx,
-        );
+    /// MIME types the current primary selection can be read as.
+    pub fn primary_selection_mime_types(&self) -> &[String] {
+        self.globals
+            .primary_selection_seat()
+            .and_then(|idx| self.globals.seats[idx].primary_selection.as_ref())
+            .and_then(|device| device.selection.as_ref())
+            .map(|offer| offer.mime_types.as_slice())
+            .unwrap_or(&[])
+    }
 
-        if let Some(window) = self.windows.get_mut(idx) {
-            for buffer in &mut window.buffers {
-                buffer.destroy = true;
-            }
+    /// Starts reading the primary selection as `mime_type`, see `read_clipboard`.
+    pub fn read_primary_selection(&self, mime_type: &str) -> Result<DataRead, ClientError> {
+        let Some(offer) = self
+            .globals
+            .primary_selection_seat()
+            .and_then(|idx| self.globals.seats[idx].primary_selection.as_ref())
+            .and_then(|device| device.selection.as_ref())
+        else {
+            return Err(ClientError::Initialization {
+                kind: ClientErrorKind::Clipboard,
+                message: "Primary selection is empty".to_string(),
+            });
+        };
+        let Some(negotiated) = negotiate_mime_type(&offer.mime_types, mime_type) else {
+            return Err(ClientError::Initialization {
+                kind: ClientErrorKind::Clipboard,
+                message: format!("Primary selection has no {mime_type} data"),
+            });
+        };
+
+        let read = offer
+            .receive(negotiated)
+            .map_err(|err| ClientError::Initialization {
+                kind: ClientErrorKind::Clipboard,
+                message: format!("Failed to read primary selection: {err}"),
+            })?;
+        self.connection.flush().map_err(DispatchError::Backend)?;
+        Ok(read)
+    }
 
-            window.buffers.push(Buffer {
-                data: buffer0,
-                offset: 0,
-                used: false,
-                destroy: false,
-                width: window_width,
-                height: window_height,
+    /// Starts dragging `data` (keyed by MIME type) out of `window`, call it while a pointer button
+    /// pressed over the window is still held. `actions` are those the drop may perform, `icon` is
+    /// a cursor from `create_cursor` drawn under the pointer. A surface takes a single role, so a
+    /// cursor already shown as a pointer cursor is refused, and one used as an icon is never shown
+    /// as a pointer cursor afterwards.
+    pub fn start_drag(
+        &mut self,
+        window: usize,
+        data: HashMap<String, Vec<u8>>,
+        actions: DndAction,
+        icon: Option<usize>,
+    ) -> Result<(), ClientError> {
+        let qhandle = self.queue.handle();
+        let Some(surface) = self
+            .globals
+            .windows
+            .get(window)
+            .filter(|window| !window.destroyed)
+            .map(|window| &window.surface)
+        else {
+            return Err(ClientError::Initialization {
+                kind: ClientErrorKind::Surface,
+                message: format!("Window {window} does not exist"),
             });
-            window.buffers.push(Buffer {
-                data: buffer1,
-                offset: buffer_size as u64,
-                used: false,
-                destroy: false,
-                width: window_width,
-                height: window_height,
+        };
+        let (Some(manager), Some(seat)) = (
+            &self.globals.data_device_manager,
+            self.globals.seats.iter().find(|seat| {
+                seat.data_device.is_some()
+                    && seat
+                        .pointer
+                        .as_ref()
+                        .is_some_and(|pointer| pointer.focus == Some(window))
+            }),
+        ) else {
+            return Err(ClientError::Initialization {
+                kind: ClientErrorKind::Clipboard,
+                message: "Failed to start drag (no pointer over the window)".to_string(),
+            });
+        };
+        let (Some(device), Some(pointer)) = (&seat.data_device, &seat.pointer) else {
+            return Err(ClientError::Initialization {
+                kind: ClientErrorKind::Clipboard,
+                message: "Failed to start drag (no pointer over the window)".to_string(),
             });
+        };
+        let icon = match icon {
+            Some(id) => match self
+                .globals
+                .custom_cursors
+                .get_mut(id)
+                .filter(|cursor| !cursor.destroyed)
+            {
+                Some(cursor) if cursor.role == Some(CursorRole::Pointer) => {
+                    return Err(ClientError::Initialization {
+                        kind: ClientErrorKind::Surface,
+                        message: format!("Cursor {id} is a pointer cursor, not a drag icon"),
+                    });
+                }
+                Some(cursor) => Some(cursor),
+                None => {
+                    return Err(ClientError::Initialization {
+                        kind: ClientErrorKind::Surface,
+                        message: format!("Cursor {id} does not exist"),
+                    });
+                }
+            },
+            None => None,
+        };
 
-            window.needs_resizing = false;
+        let wl_data_source = manager.create_data_source(&qhandle, ());
+        for mime_type in data.keys() {
+            wl_data_source.offer(mime_type.clone());
+        }
+        if wl_data_source.version() >= 3 {
+            wl_data_source.set_actions(actions);
+        }
+        device.wl_data_device.start_drag(
+            Some(&wl_data_source),
+            surface,
+            icon.as_ref().map(|cursor| &cursor.surface),
+            pointer.button_serial,
+        );
+        if let Some(cursor) = icon
+            && cursor.role.replace(CursorRole::DragIcon).is_none()
+        {
+            cursor.offset_to_hotspot();
         }
 
-        let old_pool = std::mem::replace(&mut self.windows.get_mut(idx).unwrap().pool, pool);
-        old_pool.destroy();
+        if let Some(old) = self.globals.drag_source.replace(DragSource {
+            source: DataSource {
+                wl_data_source,
+                data,
+            },
+            action: DndAction::empty(),
+        }) {
+            old.source.wl_data_source.destroy();
+        }
+        self.connection.flush().map_err(DispatchError::Backend)?;
+        Ok(())
+    }
 
-        self.windows.get_mut(idx).unwrap().needs_resizing = false;
+    /// Tells the source of the drag over a window of `seat` whether the window takes the drop:
+    /// `mime_type` is the type it would read, `None` refuses the drop. `actions` are those the
+    /// window supports, `preferred` the one it would rather have. Call it again as the pointer
+    /// moves over parts that take different data, and after a drop with `DndAction::Ask` with
+    /// the single action the user picked.
+    pub fn accept_drag(
+        &mut self,
+        seat: u32,
+        mime_type: Option<&str>,
+        actions: DndAction,
+        preferred: DndAction,
+    ) -> Result<(), ClientError> {
+        let Some(drag) = self.drag_mut(seat) else {
+            return Err(ClientError::Initialization {
+                kind: ClientErrorKind::Clipboard,
+                message: format!("No drag over a window of seat {seat}"),
+            });
+        };
 
+        let accepted = mime_type
+            .and_then(|mime_type| negotiate_mime_type(&drag.offer.mime_types, mime_type))
+            .map(str::to_string);
+        drag.offer
+            .wl_data_offer
+            .accept(drag.serial, accepted.clone());
+        drag.accepted = accepted;
+        if drag.offer.wl_data_offer.version() >= 3 {
+            drag.offer.wl_data_offer.set_actions(actions, preferred);
+        }
+        self.connection.flush().map_err(DispatchError::Backend)?;
         Ok(())
     }
 
-    fn create_pool(
-        &self,
-        qhandle: &QueueHandle<State>,
-        file: &File,
-        size: i32,
-    ) -> Result<wl_shm_pool::WlShmPool, ClientError> {
-        if let Some(shm) = &self.shm {
-            return Ok(shm.create_pool(BorrowedFd::from(file.as_fd()), size, qhandle, ()));
-        } else {
+    /// Starts reading the data dragged over or dropped on a window of `seat` as `mime_type`, see
+    /// `read_clipboard`.
+    pub fn read_drop(&mut self, seat: u32, mime_type: &str) -> Result<DataRead, ClientError> {
+        let Some(drag) = self.drag_mut(seat) else {
             return Err(ClientError::Initialization {
-                kind: ClientErrorKind::Pool,
-                message: "Failed to initialize wl_shm_pool (wl_shm not available)".to_string(),
+                kind: ClientErrorKind::Clipboard,
+                message: format!("No drag over a window of seat {seat}"),
+            });
+        };
+        let Some(negotiated) = negotiate_mime_type(&drag.offer.mime_types, mime_type) else {
+            return Err(ClientError::Initialization {
+                kind: ClientErrorKind::Clipboard,
+                message: format!("Drop has no {mime_type} data"),
             });
+        };
+
+        let read = drag
+            .offer
+            .receive(negotiated)
+            .map_err(|err| ClientError::Initialization {
+                kind: ClientErrorKind::Clipboard,
+                message: format!("Failed to read drop: {err}"),
+            })?;
+        self.connection.flush().map_err(DispatchError::Backend)?;
+        Ok(read)
+    }
+
+    /// Ends the drop on a window of `seat` once its data was read, the source then performs the
+    /// action, e.g. deletes moved files.
+    pub fn finish_drop(&mut self, seat: u32) -> Result<(), ClientError> {
+        let Some(device) = self
+            .globals
+            .seats
+            .iter_mut()
+            .find(|other| other.id == seat)
+            .and_then(|seat| seat.data_device.as_mut())
+        else {
+            return Ok(());
+        };
+        if let Some(drag) = device.drag.take_if(|drag| drag.dropped) {
+            drag.finish();
         }
+        self.connection.flush().map_err(DispatchError::Backend)?;
+        Ok(())
     }
 
-    fn create_surface(
-        &self,
-        qhandle: &QueueHandle<State>,
-    ) -> Result<wl_surface::WlSurface, ClientError> {
-        if let Some(compositor) = &self.compositor {
-            return Ok(compositor.create_surface(&qhandle, ()));
-        } else {
+    fn drag_mut(&mut self, seat: u32) -> Option<&mut DragOffer> {
+        self.globals
+            .seats
+            .iter_mut()
+            .find(|other| other.id == seat)
+            .and_then(|seat| seat.data_device.as_mut())
+            .and_then(|device| device.drag.as_mut())
+    }
+
+    /// Tablets that finished announcing their properties.
+    pub fn tablets(&self) -> impl Iterator<Item = &Tablet> {
+        self.globals
+            .seats
+            .iter()
+            .filter_map(|seat| seat.tablet.as_ref())
+            .flat_map(|tablet_seat| &tablet_seat.tablets)
+            .filter(|tablet| tablet.ready)
+    }
+
+    pub fn tablet_tools(&self) -> impl Iterator<Item = &TabletTool> {
+        self.globals
+            .seats
+            .iter()
+            .filter_map(|seat| seat.tablet.as_ref())
+            .flat_map(|tablet_seat| &tablet_seat.tools)
+            .filter(|tool| tool.ready)
+    }
+
+    pub fn tablet_pads(&self) -> impl Iterator<Item = &TabletPad> {
+        self.globals
+            .seats
+            .iter()
+            .filter_map(|seat| seat.tablet.as_ref())
+            .flat_map(|tablet_seat| &tablet_seat.pads)
+            .filter(|pad| pad.ready)
+    }
+
+    /// Outputs the window at `idx` is currently shown on.
+    pub fn window_outputs(&self, idx: usize) -> impl Iterator<Item = &Output> {
+        let ids = self
+            .globals
+            .windows
+            .get(idx)
+            .map(|window| window.outputs.as_slice())
+            .unwrap_or_default();
+        self.outputs()
+            .filter(move |output| ids.contains(&output.id))
+    }
+
+    /// Clock the presentation timestamps are in, `None` without `wp_presentation`.
+    pub fn presentation_clock(&self) -> Option<u32> {
+        self.globals.presentation_clock
+    }
+
+    pub fn set_control_flow(&mut self, control_flow: ControlFlow) {
+        self.globals.control_flow = control_flow;
+    }
+
+    /// Creates a cursor from premultiplied ARGB8888 `pixels` with the hotspot at `hotspot`.
+    /// Returns an id to use with `CursorIcon::Custom`.
+    pub fn create_cursor(
+        &mut self,
+        pixels: &[u32],
+        width: i32,
+        height: i32,
+        hotspot: (i32, i32),
+    ) -> Result<usize, ClientError> {
+        let qhandle = self.queue.handle();
+        let id = self.globals.custom_cursors.len();
+        let Some(shm) = &self.globals.shm else {
+            return Err(ClientError::Initialization {
+                kind: ClientErrorKind::Pool,
+                message: "Failed to create cursor (wl_shm not available)".to_string(),
+            });
+        };
+        let Some(compositor) = &self.globals.compositor else {
             return Err(ClientError::Initialization {
                 kind: ClientErrorKind::Surface,
-                message: "Failed to initialize wl_surface (wl_compositor not available)"
-                    .to_string(),
+                message: "Failed to create cursor (wl_compositor not available)".to_string(),
             });
         };
+
+        let mut cursor = CustomCursor::new(compositor.create_surface(&qhandle, ()));
+        if let Err(err) = cursor.set_pixels(shm, &qhandle, id, pixels, width, height, hotspot) {
+            cursor.surface.destroy();
+            return Err(err);
+        }
+
+        self.globals.custom_cursors.push(cursor);
+        Ok(id)
     }
 
-    fn create_xdg_surface(
-        &self,
-        surface: &wl_surface::WlSurface,
-        qhandle: &QueueHandle<State>,
-    ) -> Result<xdg_surface::XdgSurface, ClientError> {
-        if let Some(xdg_wm_base) = &self.xdg_wm_base {
-            return Ok(xdg_wm_base.get_xdg_surface(surface, qhandle, self.windows.len()));
-        } else {
+    /// Replaces the image of a custom cursor, windows showing it update right away.
+    pub fn set_cursor_pixels(
+        &mut self,
+        id: usize,
+        pixels: &[u32],
+        width: i32,
+        height: i32,
+        hotspot: (i32, i32),
+    ) -> Result<(), ClientError> {
+        let qhandle = self.queue.handle();
+        let (Some(shm), Some(cursor)) = (
+            &self.globals.shm,
+            self.globals
+                .custom_cursors
+                .get_mut(id)
+                .filter(|cursor| !cursor.destroyed),
+        ) else {
             return Err(ClientError::Initialization {
-                kind: ClientErrorKind::XdgSurface,
-                message: "Failed to initialize xdg_surface (xdg_wm_base not available)".to_string(),
+                kind: ClientErrorKind::Surface,
+                message: format!("Cursor {id} does not exist"),
             });
         };
-    }
-}
 
-impl Window {
-    pub fn get_available_buffer(&mut self) -> Option<&mut Buffer> {
-        self.buffers
-            .iter_mut()
-            .find(|buffer| !buffer.used && !buffer.destroy)
-    }
+        let old_hotspot = (cursor.hotspot_x, cursor.hotspot_y);
+        let moved = old_hotspot != hotspot;
+        cursor.set_pixels(shm, &qhandle, id, pixels, width, height, hotspot)?;
 
+        // a drag icon follows its hotspot by moving the surface, a pointer cursor passes it with
+        // set_cursor, so that is redone when the cursor is visible
+        if moved && cursor.role == Some(CursorRole::DragIcon) {
+            cursor.offset(old_hotspot.0 - hotspot.0, old_hotspot.1 - hotspot.1);
+        } else if moved {
+            for seat in &mut self.globals.seats {
+                if let Some(state) = seat
+                    .pointer
+                    .as_mut()
+                    .and_then(|pointer| pointer.cursor.as_mut())
+                    && state
+                        .applied
+                        .is_some_and(|(_, icon, _)| icon == CursorIcon::Custom(id))
+                {
+                    state.applied = None;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Destroys a cursor made with `create_cursor`, windows showing it go back to the default
+    /// cursor. The id stays taken so the ids of other cursors stay valid.
+    pub fn destroy_cursor(&mut self, id: usize) -> bool {
+        match self.globals.custom_cursors.get_mut(id) {
+            Some(cursor) if !cursor.destroyed => {
+                cursor.destroy();
+                for window in &mut self.globals.windows {
+                    if window.cursor == CursorIcon::Custom(id) {
+                        window.cursor = CursorIcon::Default;
+                    }
+                }
+                for seat in &mut self.globals.seats {
+                    if let Some(state) = seat
+                        .pointer
+                        .as_mut()
+                        .and_then(|pointer| pointer.cursor.as_mut())
+                        && state
+                            .applied
+                            .is_some_and(|(_, icon, _)| icon == CursorIcon::Custom(id))
+                    {
+                        state.applied = None;
+                    }
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Destroys every protocol object owned by the window. The window keeps its slot in
+    /// `globals.windows` so indices of other windows stay valid.
+    pub fn destroy_window(&mut self, idx: usize) -> bool {
+        match self.globals.windows.get_mut(idx) {
+            Some(window) if !window.destroyed => {
+                // constraints refer to the surface, they have to go first
+                window.pointer_constraint = None;
+                self.globals.update_constraints(&self.queue.handle());
+                self.globals.windows[idx].destroy();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Protocol objects created and destroyed by all windows and custom cursors, `live()` is back
+    /// at 0 once every one of them was destroyed.
+    pub fn object_stats(&self) -> ObjectStats {
+        let mut stats = ObjectStats::default();
+        for window in &self.globals.windows {
+            stats.add(&window.objects);
+        }
+        for cursor in &self.globals.custom_cursors {
+            stats.add(&cursor.objects);
+        }
+        stats
+    }
+}
+
+/// Waits up to `timeout` (forever when `None`) for `fd` to become readable or one of `writes` to
+/// take more data. Returns whether `fd` is readable.
+fn wait_readable(
+    fd: BorrowedFd,
+    writes: &[DataWrite],
+    timeout: Option<Duration>,
+) -> Result<bool, ClientError> {
+    let mut fds: Vec<PollFd> = std::iter::once(PollFd::new(&fd, PollFlags::IN))
+        .chain(
+            writes
+                .iter()
+                .map(|write| PollFd::new(write, PollFlags::OUT)),
+        )
+        .collect();
+    let timeout = timeout.map(|timeout| Timespec {
+        tv_sec: timeout.as_secs() as _,
+        tv_nsec: timeout.subsec_nanos() as _,
+    });
+
+    match poll(&mut fds, timeout.as_ref()) {
+        Ok(_) => Ok(!fds[0].revents().is_empty()),
+        Err(rustix::io::Errno::INTR) => Ok(false),
+        Err(err) => Err(DispatchError::Backend(WaylandError::Io(err.into())).into()),
+    }
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl State {
+    pub fn new() -> Self {
+        State {
+            control_flow: ControlFlow::default(),
+            compositor: None,
+            xdg_wm_base: None,
+            shm: None,
+            presentation: None,
+            presentation_clock: None,
+            xdg_output_manager: None,
+            fractional_scale_manager: None,
+            viewporter: None,
+            cursor_shape_manager: None,
+            pointer_gestures: None,
+            pointer_constraints: None,
+            relative_pointer_manager: None,
+            tablet_manager: None,
+            data_device_manager: None,
+            primary_selection_manager: None,
+            outputs: Vec::new(),
+            seats: Vec::new(),
+            cursor_themes: CursorThemes::from_env(),
+            custom_cursors: Vec::new(),
+            clipboard_source: None,
+            primary_selection_source: None,
+            drag_source: None,
+            data_writes: Vec::new(),
+            windows: Vec::new(),
+            events: VecDeque::new(),
+            error: None,
+        }
+    }
+
+    pub fn dispatch() {
+        todo!()
+    }
+
+    /// Keeps the error of a step taken while handling an event, event handlers can't return it.
+    /// Only the first one is kept until `Client::dispatch` returns it.
+    pub fn store_error(&mut self, result: Result<(), ClientError>) {
+        if let Err(err) = result
+            && self.error.is_none()
+        {
+            self.error = Some(err);
+        }
+    }
+
+    /// Creates or releases the devices of the seat at index `seat` in `seats` to match
+    /// `capabilities`.
+    pub fn update_capabilities(
+        &mut self,
+        qhandle: &QueueHandle<State>,
+        seat: usize,
+        capabilities: wl_seat::Capability,
+    ) {
+        let seat = &mut self.seats[seat];
+        let added = capabilities - seat.capabilities;
+        let removed = seat.capabilities - capabilities;
+        seat.capabilities = capabilities;
+
+        let has_keyboard = capabilities.contains(wl_seat::Capability::Keyboard);
+        if has_keyboard && seat.keyboard.is_none() {
+            seat.keyboard = Some(Keyboard::new(seat.wl_seat.get_keyboard(qhandle, seat.id)));
+        } else if !has_keyboard && let Some(keyboard) = seat.keyboard.take() {
+            if keyboard.wl_keyboard.version() >= 3 {
+                keyboard.wl_keyboard.release();
+            }
+            if let Some(window) = keyboard.focus {
+                self.events.push_back(Event::KeyboardLeave {
+                    window,
+                    seat: seat.id,
+                });
+            }
+        }
+
+        let has_pointer = capabilities.contains(wl_seat::Capability::Pointer);
+        if has_pointer && seat.pointer.is_none() {
+            let mut pointer = Pointer::new(seat.wl_seat.get_pointer(qhandle, seat.id));
+            pointer.cursor_shape_device = self
+                .cursor_shape_manager
+                .as_ref()
+                .map(|manager| manager.get_pointer(&pointer.wl_pointer, qhandle, ()));
+            pointer.gestures = self.pointer_gestures.as_ref().map(|manager| Gestures {
+                swipe: manager.get_swipe_gesture(&pointer.wl_pointer, qhandle, seat.id),
+                pinch: manager.get_pinch_gesture(&pointer.wl_pointer, qhandle, seat.id),
+                hold: (manager.version() >= 3)
+                    .then(|| manager.get_hold_gesture(&pointer.wl_pointer, qhandle, seat.id)),
+                focus: None,
+            });
+            pointer.relative_pointer = self
+                .relative_pointer_manager
+                .as_ref()
+                .map(|manager| manager.get_relative_pointer(&pointer.wl_pointer, qhandle, seat.id));
+            seat.pointer = Some(pointer);
+        } else if !has_pointer && let Some(pointer) = seat.pointer.take() {
+            if pointer.wl_pointer.version() >= 3 {
+                pointer.wl_pointer.release();
+            }
+            if let Some(cursor) = &pointer.cursor {
+                cursor.surface.destroy();
+            }
+            if let Some(device) = &pointer.cursor_shape_device {
+                device.destroy();
+            }
+            if let Some(gestures) = &pointer.gestures {
+                gestures.destroy();
+            }
+            if let Some(relative_pointer) = &pointer.relative_pointer {
+                relative_pointer.destroy();
+            }
+            for applied in &pointer.constraints {
+                applied.destroy();
+            }
+            if let Some(window) = pointer.focus {
+                self.events.push_back(Event::Pointer {
+                    window,
+                    seat: seat.id,
+                    events: vec![PointerEvent::Leave { serial: 0 }],
+                });
+            }
+        }
+
+        let has_touch = capabilities.contains(wl_seat::Capability::Touch);
+        if has_touch && seat.touch.is_none() {
+            seat.touch = Some(Touch::new(seat.wl_seat.get_touch(qhandle, seat.id)));
+        } else if !has_touch && let Some(mut touch) = seat.touch.take() {
+            if touch.wl_touch.version() >= 3 {
+                touch.wl_touch.release();
+            }
+            touch.pending.clear();
+            touch.cancel();
+            for (window, events) in touch.take_frame() {
+                self.events.push_back(Event::Touch {
+                    window,
+                    seat: seat.id,
+                    events,
+                });
+            }
+        }
+
+        if !(added.is_empty() && removed.is_empty()) {
+            self.events.push_back(Event::SeatCapabilitiesChanged {
+                seat: seat.id,
+                added,
+                removed,
+            });
+        }
+    }
+
+    /// Seat the clipboard is used through: the one with keyboard focus, else the first one with a
+    /// data device.
+    pub fn clipboard_seat(&self) -> Option<usize> {
+        self.selection_seat(|seat| seat.data_device.is_some())
+    }
+
+    /// Same as `clipboard_seat`, for seats with a primary selection device.
+    pub fn primary_selection_seat(&self) -> Option<usize> {
+        self.selection_seat(|seat| seat.primary_selection.is_some())
+    }
+
+    fn selection_seat(&self, usable: impl Fn(&Seat) -> bool) -> Option<usize> {
+        self.seats
+            .iter()
+            .position(|seat| {
+                usable(seat)
+                    && seat
+                        .keyboard
+                        .as_ref()
+                        .is_some_and(|keyboard| keyboard.focus.is_some())
+            })
+            .or_else(|| self.seats.iter().position(&usable))
+    }
+
+    /// Earliest moment a client-side timer needs to fire.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.seats
+            .iter()
+            .flat_map(|seat| {
+                let key_repeat = seat
+                    .keyboard
+                    .as_ref()
+                    .and_then(|keyboard| keyboard.repeat.as_ref())
+                    .map(|repeat| repeat.next);
+                let cursor_frame = seat
+                    .pointer
+                    .as_ref()
+                    .and_then(|pointer| pointer.cursor.as_ref())
+                    .and_then(|cursor| cursor.next_frame);
+                key_repeat.into_iter().chain(cursor_frame)
+            })
+            .min()
+    }
+
+    /// Moves the pending data writes along. A write that fails is dropped, which closes its fd
+    /// and ends the transfer early for the reader.
+    pub fn write_data(&mut self) {
+        self.data_writes
+            .retain_mut(|write| matches!(write.poll(), Ok(false)));
+    }
+
+    /// Runs the client-side timers that are due at `now`.
+    pub fn fire_timers(&mut self, now: Instant) {
+        for seat in &mut self.seats {
+            let Some(keyboard) = seat.keyboard.as_mut() else {
+                continue;
+            };
+
+            if let Some((window, key)) = keyboard.poll_repeat(now) {
+                let text = key.text.clone();
+                self.events.push_back(Event::KeyPressed {
+                    window,
+                    seat: seat.id,
+                    key,
+                });
+                if let Some(text) = text {
+                    self.events.push_back(Event::Text {
+                        window,
+                        seat: seat.id,
+                        text,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Queues a gesture event. `surface` is given by begin events and sets the window the rest of
+    /// the gesture goes to.
+    pub fn push_gesture(
+        &mut self,
+        seat: u32,
+        surface: Option<&wl_surface::WlSurface>,
+        event: GestureEvent,
+    ) {
+        let Some(gestures) = self
+            .seats
+            .iter_mut()
+            .find(|other| other.id == seat)
+            .and_then(|seat| seat.pointer.as_mut())
+            .and_then(|pointer| pointer.gestures.as_mut())
+        else {
+            return;
+        };
+        if let Some(surface) = surface {
+            gestures.focus = surface.data::<usize>().copied();
+        }
+        if let Some(window) = gestures.focus {
+            self.events.push_back(Event::Gesture {
+                window,
+                seat,
+                event,
+            });
+        }
+    }
+
+    /// Creates, replaces or destroys constraint objects so they match what the windows ask for.
+    pub fn update_constraints(&mut self, qhandle: &QueueHandle<State>) {
+        for seat in 0..self.seats.len() {
+            self.update_seat_constraints(seat, qhandle);
+        }
+    }
+
+    fn update_seat_constraints(&mut self, seat: usize, qhandle: &QueueHandle<State>) {
+        let seat = &mut self.seats[seat];
+        let seat_id = seat.id;
+        let Some(pointer) = seat.pointer.as_mut() else {
+            return;
+        };
+        let Some(manager) = &self.pointer_constraints else {
+            return;
+        };
+        let wanted = |window: usize| {
+            self.windows
+                .get(window)
+                .filter(|window| !window.destroyed)
+                .and_then(|window| window.pointer_constraint)
+        };
+
+        pointer.constraints.retain(|applied| {
+            let keep = wanted(applied.window) == Some(applied.constraint);
+            if !keep {
+                applied.destroy();
+                if applied.active {
+                    self.events.push_back(match applied.constraint.kind {
+                        ConstraintKind::Lock => Event::PointerUnlocked {
+                            window: applied.window,
+                            seat: seat_id,
+                        },
+                        ConstraintKind::Confine => Event::PointerUnconfined {
+                            window: applied.window,
+                            seat: seat_id,
+                        },
+                    });
+                }
+            }
+            keep
+        });
+
+        for (idx, window) in self.windows.iter().enumerate() {
+            let Some(constraint) = wanted(idx) else {
+                continue;
+            };
+            if pointer
+                .constraints
+                .iter()
+                .any(|applied| applied.window == idx)
+            {
+                continue;
+            }
+
+            let region = constraint.region.and_then(|rect| {
+                let region = self.compositor.as_ref()?.create_region(qhandle, ());
+                region.add(rect.x, rect.y, rect.width, rect.height);
+                Some(region)
+            });
+            let lifetime = zwp_pointer_constraints_v1::Lifetime::Persistent;
+            let object = match constraint.kind {
+                ConstraintKind::Lock => ConstraintObject::Locked(manager.lock_pointer(
+                    &window.surface,
+                    &pointer.wl_pointer,
+                    region.as_ref(),
+                    lifetime,
+                    qhandle,
+                    ConstraintData {
+                        seat: seat_id,
+                        window: idx,
+                    },
+                )),
+                ConstraintKind::Confine => ConstraintObject::Confined(manager.confine_pointer(
+                    &window.surface,
+                    &pointer.wl_pointer,
+                    region.as_ref(),
+                    lifetime,
+                    qhandle,
+                    ConstraintData {
+                        seat: seat_id,
+                        window: idx,
+                    },
+                )),
+            };
+            // the compositor copies the region, it is not needed afterwards
+            if let Some(region) = region {
+                region.destroy();
+            }
+
+            pointer.constraints.push(AppliedConstraint {
+                window: idx,
+                constraint,
+                object,
+                active: false,
+            });
+        }
+    }
+
+    /// Records a `locked`/`confined` or `unlocked`/`unconfined` event for the window.
+    pub fn set_constraint_active(&mut self, data: &ConstraintData, active: bool) {
+        if let Some(applied) = self
+            .seats
+            .iter_mut()
+            .find(|seat| seat.id == data.seat)
+            .and_then(|seat| seat.pointer.as_mut())
+            .and_then(|pointer| {
+                pointer
+                    .constraints
+                    .iter_mut()
+                    .find(|applied| applied.window == data.window)
+            })
+        {
+            applied.active = active;
+        }
+    }
+
+    /// Shows the cursor icon of the window under the pointer, and advances animated cursors.
+    pub fn update_cursor(&mut self, conn: &Connection, qhandle: &QueueHandle<State>, now: Instant) {
+        for seat in 0..self.seats.len() {
+            self.update_seat_cursor(seat, conn, qhandle, now);
+        }
+    }
+
+    fn update_seat_cursor(
+        &mut self,
+        seat: usize,
+        conn: &Connection,
+        qhandle: &QueueHandle<State>,
+        now: Instant,
+    ) {
+        let Some(pointer) = self.seats[seat].pointer.as_mut() else {
+            return;
+        };
+        let Some(idx) = pointer.focus else {
+            return;
+        };
+        let Some(window) = self.windows.get(idx) else {
+            return;
+        };
+
+        let icon = window.cursor;
+        let scale = window.scale_factor().ceil() as i32;
+        let wanted = Some((idx, icon, scale));
+
+        if let Some(device) = &pointer.cursor_shape_device
+            && let Some(shape) = icon.shape()
+        {
+            if pointer.cursor_shape_applied != wanted {
+                device.set_shape(pointer.enter_serial, shape);
+                pointer.cursor_shape_applied = wanted;
+                // a themed cursor set later has to be set again
+                if let Some(cursor) = pointer.cursor.as_mut() {
+                    cursor.applied = None;
+                    cursor.next_frame = None;
+                }
+            }
+            return;
+        }
+        pointer.cursor_shape_applied = None;
+
+        let cursor = match pointer.cursor.as_mut() {
+            Some(cursor) => cursor,
+            None => {
+                let Some(compositor) = &self.compositor else {
+                    return;
+                };
+                pointer
+                    .cursor
+                    .insert(CursorState::new(compositor.create_surface(qhandle, ())))
+            }
+        };
+
+        let frame_due = cursor.next_frame.is_some_and(|next| next <= now);
+        if cursor.applied == wanted && !frame_due {
+            return;
+        }
+        if cursor.applied != wanted {
+            cursor.started = now;
+        }
+        cursor.applied = wanted;
+        cursor.next_frame = None;
+
+        if icon == CursorIcon::Hidden {
+            pointer
+                .wl_pointer
+                .set_cursor(pointer.enter_serial, None, 0, 0);
+            return;
+        }
+        if let CursorIcon::Custom(id) = icon {
+            // a drag icon can't take the cursor role, the pointer keeps the cursor it had
+            if let Some(custom) = self.custom_cursors.get_mut(id)
+                && !custom.destroyed
+                && custom.role != Some(CursorRole::DragIcon)
+            {
+                custom.role = Some(CursorRole::Pointer);
+                pointer.wl_pointer.set_cursor(
+                    pointer.enter_serial,
+                    Some(&custom.surface),
+                    custom.hotspot_x,
+                    custom.hotspot_y,
+                );
+            }
+            return;
+        }
+
+        let Some(shm) = &self.shm else {
+            return;
+        };
+        let elapsed = now - cursor.started;
+        let mut image_for = |scale: i32| {
+            self.cursor_themes
+                .image(conn, shm, icon, scale, elapsed)
+                .or_else(|| {
+                    self.cursor_themes
+                        .image(conn, shm, CursorIcon::Default, scale, elapsed)
+                })
+        };
+
+        // themes without an image size matching the scale fall back to unscaled images
+        let mut shown = image_for(scale).filter(|image| cursor.attach(image, scale));
+        let mut shown_scale = scale;
+        if shown.is_none() && scale != 1 {
+            shown = image_for(1).filter(|image| cursor.attach(image, 1));
+            shown_scale = 1;
+        }
+        // not even the default cursor is in the theme, keep whatever the pointer shows now
+        let Some(image) = shown else {
+            return;
+        };
+
+        pointer.wl_pointer.set_cursor(
+            pointer.enter_serial,
+            Some(&cursor.surface),
+            image.hotspot_x / shown_scale,
+            image.hotspot_y / shown_scale,
+        );
+        cursor.next_frame = image.duration.map(|duration| now + duration);
+    }
+
+    pub fn resize_buffer(
+        &mut self,
+        qhandle: &QueueHandle<State>,
+        idx: usize,
+    ) -> Result<(), ClientError> {
+        let pixel_format = super::DEFAULT_PIXEL_FORMAT;
+        let pixel_size = match bytes_per_pixel(pixel_format) {
+            Ok(bytes) => bytes,
+            Err(_) => {
+                return Err(ClientError::Initialization {
+                    kind: ClientErrorKind::Pixel,
+                    message: "Pixel format not found".to_string(),
+                });
+            }
+        };
+
+        let missing = || ClientError::Initialization {
+            kind: ClientErrorKind::Surface,
+            message: format!("Window {idx} does not exist"),
+        };
+        let (window_width, window_height) = self
+            .windows
+            .get(idx)
+            .filter(|window| !window.destroyed)
+            .ok_or_else(missing)?
+            .physical_size();
+
+        let stride = window_width * pixel_size;
+        let buffer_size = stride * window_height;
+        let total_size = buffer_size * 2;
+
+        let mut file = tempfile::tempfile()?;
+        file.set_len((total_size) as u64)?;
+        file.rewind()?;
+        let mapping = ShmMapping::new(&file, total_size as usize)?;
+        let pool = Self::create_pool(self, qhandle, &file, total_size)?;
+
+        let buffer0 = pool.create_buffer(
+            0,
+            window_width,
+            window_height,
+            stride,
+            pixel_format,
+            qhandle,
+            idx,
+        );
+
+        let buffer1 = pool.create_buffer(
+            buffer_size,
+            window_width,
+            window_height,
+            stride,
+            pixel_format,
+            qhandle,
+            idx,
+        );
+
+        let window = self.windows.get_mut(idx).ok_or_else(missing)?;
+        window.file = file;
+        window.mapping = mapping;
+
+        for buffer in &mut window.buffers {
+            buffer.destroy = true;
+        }
+
+        window.buffers.push(Buffer {
+            data: buffer0,
+            offset: 0,
+            used: false,
+            destroy: false,
+            width: window_width,
+            height: window_height,
+            frame: 0,
+        });
+        window.buffers.push(Buffer {
+            data: buffer1,
+            offset: buffer_size as u64,
+            used: false,
+            destroy: false,
+            width: window_width,
+            height: window_height,
+            frame: 0,
+        });
+        window.objects.buffers.created += 2;
+
+        window.needs_resizing = false;
+        window.redraw_requested = true;
+        window.cleanup_buffers();
+
+        let old_pool = std::mem::replace(&mut window.pool, pool);
+        old_pool.destroy();
+        window.objects.pools.created += 1;
+        window.objects.pools.destroyed += 1;
+
+        Ok(())
+    }
+
+    /// Picks the buffer scale of a window: the compositor's preferred fractional scale when a
+    /// viewport is available, then its preferred integer scale, otherwise the largest scale of the
+    /// outputs the window is on. Buffers are reallocated and `ScaleChanged` is emitted when it
+    /// changes.
+    pub fn update_scale(
+        &mut self,
+        qhandle: &QueueHandle<State>,
+        idx: usize,
+    ) -> Result<(), ClientError> {
+        let Some(window) = self.windows.get(idx) else {
+            return Ok(());
+        };
+
+        let (scale, fractional_scale) = match (window.preferred_fractional_scale, &window.viewport)
+        {
+            (Some(fractional), Some(_)) if fractional > 0 => (1, Some(fractional)),
+            _ => (
+                window.preferred_scale.unwrap_or_else(|| {
+                    self.outputs
+                        .iter()
+                        .filter(|output| window.outputs.contains(&output.id))
+                        .map(|output| output.scale)
+                        .max()
+                        .unwrap_or(1)
+                }),
+                None,
+            ),
+        };
+
+        if scale < 1
+            || (scale == window.scale && fractional_scale == window.fractional_scale)
+            || window.destroyed
+        {
+            return Ok(());
+        }
+
+        let Some(window) = self.windows.get_mut(idx) else {
+            return Ok(());
+        };
+        window.scale = scale;
+        window.fractional_scale = fractional_scale;
+        let factor = window.scale_factor();
+
+        self.events.push_back(Event::ScaleChanged {
+            window: idx,
+            factor,
+        });
+        self.resize_buffer(qhandle, idx)
+    }
+
+    fn create_pool(
+        &self,
+        qhandle: &QueueHandle<State>,
+        file: &File,
+        size: i32,
+    ) -> Result<wl_shm_pool::WlShmPool, ClientError> {
+        if let Some(shm) = &self.shm {
+            Ok(shm.create_pool(file.as_fd(), size, qhandle, ()))
+        } else {
+            Err(ClientError::Initialization {
+                kind: ClientErrorKind::Pool,
+                message: "Failed to initialize wl_shm_pool (wl_shm not available)".to_string(),
+            })
+        }
+    }
+
+    fn create_surface(
+        &self,
+        qhandle: &QueueHandle<State>,
+    ) -> Result<wl_surface::WlSurface, ClientError> {
+        if let Some(compositor) = &self.compositor {
+            Ok(compositor.create_surface(qhandle, self.windows.len()))
+        } else {
+            Err(ClientError::Initialization {
+                kind: ClientErrorKind::Surface,
+                message: "Failed to initialize wl_surface (wl_compositor not available)"
+                    .to_string(),
+            })
+        }
+    }
+
+    fn create_xdg_surface(
+        &self,
+        surface: &wl_surface::WlSurface,
+        qhandle: &QueueHandle<State>,
+    ) -> Result<xdg_surface::XdgSurface, ClientError> {
+        if let Some(xdg_wm_base) = &self.xdg_wm_base {
+            Ok(xdg_wm_base.get_xdg_surface(surface, qhandle, self.windows.len()))
+        } else {
+            Err(ClientError::Initialization {
+                kind: ClientErrorKind::XdgSurface,
+                message: "Failed to initialize xdg_surface (xdg_wm_base not available)".to_string(),
+            })
+        }
+    }
+}
+
+impl Window {
+    pub fn get_available_buffer(&mut self) -> Option<&mut Buffer> {
+        let (width, height) = self.physical_size();
+        self.buffers.iter_mut().find(|buffer| {
+            !buffer.used && !buffer.destroy && buffer.width == width && buffer.height == height
+        })
+    }
+
+    /// Sets the cursor shown over this window, it is applied on the next `Client::dispatch`. A
+    /// custom cursor already used as a drag icon isn't shown.
+    pub fn set_cursor(&mut self, icon: CursorIcon) {
+        self.cursor = icon;
+    }
+
+    /// Locks the pointer in place while it is over the window, motion is then only reported
+    /// through `PointerEvent::RelativeMotion`. `region` limits where the lock may activate, in
+    /// logical coordinates.
+    pub fn lock_pointer(&mut self, region: Option<Rect>) {
+        self.pointer_constraint = Some(PointerConstraint {
+            kind: ConstraintKind::Lock,
+            region,
+        });
+    }
+
+    /// Keeps the pointer inside `region` (the whole window for `None`) once it enters it.
+    pub fn confine_pointer(&mut self, region: Option<Rect>) {
+        self.pointer_constraint = Some(PointerConstraint {
+            kind: ConstraintKind::Confine,
+            region,
+        });
+    }
+
+    /// Drops a lock or confinement set up by `lock_pointer`/`confine_pointer`.
+    pub fn release_pointer(&mut self) {
+        self.pointer_constraint = None;
+    }
+
+    pub fn logical_size(&self) -> (i32, i32) {
+        (self.width, self.height)
+    }
+
+    /// Size of the buffers in pixels.
+    pub fn physical_size(&self) -> (i32, i32) {
+        match self.fractional_scale {
+            // rounding half away from zero as wp_fractional_scale_v1 asks for
+            Some(fractional) => (
+                (self.width * fractional as i32 + 60) / 120,
+                (self.height * fractional as i32 + 60) / 120,
+            ),
+            None => (self.width * self.scale, self.height * self.scale),
+        }
+    }
+
+    /// Ratio between physical and logical pixels.
+    pub fn scale_factor(&self) -> f64 {
+        match self.fractional_scale {
+            Some(fractional) => fractional as f64 / 120.0,
+            None => self.scale as f64,
+        }
+    }
+
+    /// Asks for a new frame. The window reports `should_redraw` once the compositor is ready for
+    /// it, which is right away when no frame callback is pending.
+    pub fn request_redraw(&mut self) {
+        // an idle window starts a new burst of frames, the gap before it isn't a missed frame
+        if self.frame.is_none() && !self.redraw_requested {
+            self.frame_stats.last_time = None;
+        }
+        self.redraw_requested = true;
+    }
+
+    pub fn frame_stats(&self) -> &FrameStats {
+        &self.frame_stats
+    }
+
+    pub fn should_redraw(&self) -> bool {
+        self.redraw_requested && self.frame.is_none() && !self.destroyed
+    }
+
+    /// Acquires a free buffer at the current size, lets `draw` write into it and then attaches,
+    /// damages and commits it. Returns `Ok(None)` when every buffer is still held by the
+    /// compositor.
+    pub fn draw<R>(
+        &mut self,
+        draw: impl FnOnce(&mut Canvas<'_>) -> R,
+    ) -> Result<Option<R>, ClientError> {
+        if self.destroyed {
+            return Ok(None);
+        }
+
+        let format = super::DEFAULT_PIXEL_FORMAT;
+        let pixel_size = match bytes_per_pixel(format) {
+            Ok(bytes) => bytes,
+            Err(_) => {
+                return Err(ClientError::Initialization {
+                    kind: ClientErrorKind::Pixel,
+                    message: "Pixel format not found".to_string(),
+                });
+            }
+        };
+
+        let (width, height) = self.physical_size();
+        let Some(buffer) = self.buffers.iter_mut().find(|buffer| {
+            !buffer.used && !buffer.destroy && buffer.width == width && buffer.height == height
+        }) else {
+            return Ok(None);
+        };
+
+        let bounds = Rect::new(0, 0, buffer.width, buffer.height);
+        let age = match buffer.frame {
+            0 => 0,
+            frame => self.frame_count - frame + 1,
+        };
+        let stale = if age == 0 || age as usize > self.damage_history.len() + 1 {
+            vec![bounds]
+        } else {
+            let recent: Vec<Rect> = self
+                .damage_history
+                .iter()
+                .take(age as usize - 1)
+                .flatten()
+                .copied()
+                .collect();
+            damage::normalize(&recent, &bounds)
+        };
+
+        let stride = buffer.width * pixel_size;
+        let Some(data) = self
+            .mapping
+            .slice_mut(buffer.offset as usize, (stride * buffer.height) as usize)
+        else {
+            return Err(ClientError::Initialization {
+                kind: ClientErrorKind::File,
+                message: "Buffer lies outside the mapped pool".to_string(),
+            });
+        };
+        let mut canvas = Canvas {
+            data,
+            width: buffer.width,
+            height: buffer.height,
+            stride,
+            format,
+            age: age.min(u32::MAX as u64) as u32,
+            stale,
+            damage: Vec::new(),
+        };
+
+        let result = draw(&mut canvas);
+
+        let damage = if canvas.damage.is_empty() {
+            vec![bounds]
+        } else {
+            damage::normalize(&canvas.damage, &bounds)
+        };
+
+        self.surface.attach(Some(&buffer.data), 0, 0);
+        if self.surface.version() >= 3 {
+            self.surface.set_buffer_scale(self.scale);
+        }
+        if let Some(viewport) = &self.viewport {
+            viewport.set_destination(self.width, self.height);
+        }
+        for rect in &damage {
+            self.surface
+                .damage_buffer(rect.x, rect.y, rect.width, rect.height);
+        }
+        if self.frame.is_none() {
+            self.frame = Some(self.surface.frame(&self.qhandle, self.idx));
+        }
+        self.frame_count += 1;
+        if let Some(presentation) = &self.presentation {
+            presentation.feedback(
+                &self.surface,
+                &self.qhandle,
+                FeedbackData {
+                    window: self.idx,
+                    frame: self.frame_count,
+                },
+            );
+        }
+        self.surface.commit();
+        buffer.used = true;
+        self.redraw_requested = false;
+
+        buffer.frame = self.frame_count;
+        self.damage_history.push_front(damage);
+        self.damage_history.truncate(MAX_BUFFER_AGE);
+
+        Ok(Some(result))
+    }
+
+    /// Destroys buffers that are marked for destruction and no longer held by the compositor.
+    /// Buffers still in use are kept until their `wl_buffer.release` arrives.
     pub fn cleanup_buffers(&mut self) {
-        self.buffers.retain(|buffer| !buffer.destroy && buffer.used);
+        let (stale, live): (Vec<Buffer>, Vec<Buffer>) = std::mem::take(&mut self.buffers)
+            .into_iter()
+            .partition(|buffer| buffer.destroy && !buffer.used);
+
+        self.buffers = live;
+        self.objects.buffers.destroyed += stale.len();
+        for buffer in stale {
+            buffer.destroy();
+        }
+    }
+
+    /// Tears down the window: destroys all of its buffers, the pool, and the shell and surface
+    /// objects. Buffers still attached are destroyed too, since the surface goes away with them.
+    pub fn destroy(&mut self) {
+        if self.destroyed {
+            return;
+        }
+
+        for buffer in &mut self.buffers {
+            buffer.destroy = true;
+            buffer.used = false;
+        }
+        self.cleanup_buffers();
+
+        self.pool.destroy();
+        self.objects.pools.destroyed += 1;
+        if let Some(viewport) = self.viewport.take() {
+            viewport.destroy();
+            self.objects.viewports.destroyed += 1;
+        }
+        if let Some(wp_fractional_scale) = self.wp_fractional_scale.take() {
+            wp_fractional_scale.destroy();
+            self.objects.fractional_scales.destroyed += 1;
+        }
+        self.xdg_toplevel.destroy();
+        self.objects.xdg_toplevels.destroyed += 1;
+        self.xdg_surface.destroy();
+        self.objects.xdg_surfaces.destroyed += 1;
+        self.surface.destroy();
+        self.objects.surfaces.destroyed += 1;
+
+        self.destroyed = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::data_device::DataDevice;
+    use super::super::testing::inert_connection;
+    use super::*;
+    use wayland_client::protocol::{wl_data_device, wl_pointer};
+
+    /// Client whose globals are inert proxies: requests on them are dropped, so no compositor is
+    /// needed, while the client-side bookkeeping runs as usual.
+    fn inert_client() -> Client {
+        let connection = inert_connection();
+        let backend = connection.backend().downgrade();
+
+        let mut globals = State::new();
+        globals.compositor = Some(wl_compositor::WlCompositor::inert(backend.clone()));
+        globals.shm = Some(wl_shm::WlShm::inert(backend.clone()));
+        globals.xdg_wm_base = Some(xdg_wm_base::XdgWmBase::inert(backend.clone()));
+        globals.fractional_scale_manager = Some(
+            wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1::inert(backend.clone()),
+        );
+        globals.viewporter = Some(wp_viewporter::WpViewporter::inert(backend));
+
+        Client {
+            display: connection.display(),
+            queue: connection.new_event_queue(),
+            connection,
+            globals,
+        }
+    }
+
+    #[test]
+    fn destroy_window_releases_every_object() {
+        let mut client = inert_client();
+        let idx = client.create_window("test", "test").unwrap();
+
+        let stats = client.object_stats();
+        assert_eq!(stats.surfaces.live(), 1);
+        assert_eq!(stats.xdg_surfaces.live(), 1);
+        assert_eq!(stats.xdg_toplevels.live(), 1);
+        assert_eq!(stats.pools.live(), 1);
+        assert_eq!(stats.buffers.live(), 1);
+        assert_eq!(stats.viewports.live(), 1);
+        assert_eq!(stats.fractional_scales.live(), 1);
+
+        client.globals.windows[idx].width = 64;
+        client.globals.windows[idx].height = 32;
+        client
+            .globals
+            .resize_buffer(&client.queue.handle(), idx)
+            .unwrap();
+        assert_eq!(client.object_stats().pools.live(), 1);
+
+        assert!(client.destroy_window(idx));
+        assert!(!client.destroy_window(idx));
+        let stats = client.object_stats();
+        assert_eq!(stats.live(), 0);
+        assert_eq!(stats.buffers.created, 3);
+        assert_eq!(stats.pools.created, 2);
+    }
+
+    #[test]
+    fn only_the_first_event_error_is_kept() {
+        let mut state = State::new();
+        let error = |message: &str| ClientError::Initialization {
+            kind: ClientErrorKind::File,
+            message: message.to_string(),
+        };
+        state.store_error(Ok(()));
+        assert!(state.error.is_none());
+
+        state.store_error(Err(error("first")));
+        state.store_error(Err(error("second")));
+        assert_eq!(
+            state.error.take().map(|err| err.to_string()),
+            Some("first".to_string())
+        );
+    }
+
+    #[test]
+    fn redraw_waits_for_the_frame_callback() {
+        let mut client = inert_client();
+        let idx = client.create_window("test", "test").unwrap();
+        client
+            .globals
+            .resize_buffer(&client.queue.handle(), idx)
+            .unwrap();
+        let window = &mut client.globals.windows[idx];
+
+        // the first commit asked for a frame callback that hasn't fired yet
+        assert!(window.redraw_requested);
+        assert!(!window.should_redraw());
+
+        window.frame = None;
+        assert!(window.should_redraw());
+
+        window.draw(|_| ()).unwrap();
+        assert!(!window.redraw_requested);
+        assert!(window.frame.is_some());
+        assert!(!window.should_redraw());
+
+        window.frame = None;
+        assert!(!window.should_redraw());
+        window.request_redraw();
+        assert!(window.should_redraw());
+
+        client.destroy_window(idx);
+        assert!(!client.globals.windows[idx].should_redraw());
+    }
+
+    #[test]
+    fn draw_writes_into_the_pool() {
+        let mut client = inert_client();
+        let idx = client.create_window("test", "test").unwrap();
+        client.globals.windows[idx].width = 4;
+        client.globals.windows[idx].height = 2;
+        client
+            .globals
+            .resize_buffer(&client.queue.handle(), idx)
+            .unwrap();
+
+        let window = &mut client.globals.windows[idx];
+        let drawn = window.draw(|canvas| {
+            assert_eq!(canvas.bytes_per_pixel(), 4);
+            assert_eq!(canvas.data.len(), 4 * 4 * 2);
+            canvas.fill(0xff112233);
+        });
+        assert!(matches!(drawn, Ok(Some(()))));
+
+        let mut pixels = vec![0; 4 * 4 * 2];
+        std::os::unix::fs::FileExt::read_exact_at(&window.file, &mut pixels, 0).unwrap();
+        assert!(
+            pixels
+                .chunks(4)
+                .all(|pixel| pixel == [0x33, 0x22, 0x11, 0xff])
+        );
+    }
+
+    /// Adds a seat whose pointer is over `window`, so drags can start from it.
+    fn pointer_over(client: &mut Client, window: usize) {
+        let backend = client.connection.backend().downgrade();
+        client.globals.data_device_manager = Some(
+            wl_data_device_manager::WlDataDeviceManager::inert(backend.clone()),
+        );
+        let mut seat = Seat::new(1, wl_seat::WlSeat::inert(backend.clone()));
+        let mut pointer = Pointer::new(wl_pointer::WlPointer::inert(backend.clone()));
+        pointer.focus = Some(window);
+        seat.pointer = Some(pointer);
+        seat.data_device = Some(DataDevice::new(wl_data_device::WlDataDevice::inert(
+            backend,
+        )));
+        client.globals.seats.push(seat);
+    }
+
+    #[test]
+    fn pointer_cursor_is_refused_as_drag_icon() {
+        let mut client = inert_client();
+        let window = client.create_window("test", "test").unwrap();
+        pointer_over(&mut client, window);
+        let cursor = client.create_cursor(&[0; 4], 2, 2, (1, 1)).unwrap();
+
+        client.globals.windows[window].set_cursor(CursorIcon::Custom(cursor));
+        client
+            .globals
+            .update_cursor(&client.connection, &client.queue.handle(), Instant::now());
+        assert_eq!(
+            client.globals.custom_cursors[cursor].role,
+            Some(CursorRole::Pointer)
+        );
+
+        let drag = client.start_drag(window, HashMap::new(), DndAction::Copy, Some(cursor));
+        assert!(drag.is_err());
+        assert!(client.globals.drag_source.is_none());
+    }
+
+    #[test]
+    fn drag_icon_is_not_shown_as_pointer_cursor() {
+        let mut client = inert_client();
+        let window = client.create_window("test", "test").unwrap();
+        pointer_over(&mut client, window);
+        let cursor = client.create_cursor(&[0; 4], 2, 2, (1, 1)).unwrap();
+
+        client
+            .start_drag(window, HashMap::new(), DndAction::Copy, Some(cursor))
+            .unwrap();
+        assert_eq!(
+            client.globals.custom_cursors[cursor].role,
+            Some(CursorRole::DragIcon)
+        );
+
+        client.globals.windows[window].set_cursor(CursorIcon::Custom(cursor));
+        client
+            .globals
+            .update_cursor(&client.connection, &client.queue.handle(), Instant::now());
+        assert_eq!(
+            client.globals.custom_cursors[cursor].role,
+            Some(CursorRole::DragIcon)
+        );
+
+        // the surface keeps its role, so it can be the icon of the next drag too
+        client
+            .start_drag(window, HashMap::new(), DndAction::Copy, Some(cursor))
+            .unwrap();
+    }
+
+    #[test]
+    fn client_can_move_to_another_thread() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Client>();
+    }
+
+    #[test]
+    fn oversized_cursor_is_refused() {
+        let mut client = inert_client();
+        assert!(
+            client
+                .create_cursor(&[], i32::MAX, i32::MAX, (0, 0))
+                .is_err()
+        );
+        // fits a usize, but not the i32 size of a pool
+        assert!(client.create_cursor(&[], 65536, 65536, (0, 0)).is_err());
+        assert!(client.create_cursor(&[0; 4], 2, -2, (0, 0)).is_err());
+        assert_eq!(client.object_stats().live(), 0);
+    }
+
+    #[test]
+    fn destroyed_cursor_is_no_longer_shown() {
+        let mut client = inert_client();
+        let window = client.create_window("test", "test").unwrap();
+        pointer_over(&mut client, window);
+        let cursor = client.create_cursor(&[0; 4], 2, 2, (1, 1)).unwrap();
+        client.globals.windows[window].set_cursor(CursorIcon::Custom(cursor));
+        client
+            .globals
+            .update_cursor(&client.connection, &client.queue.handle(), Instant::now());
+
+        assert!(client.destroy_cursor(cursor));
+        assert!(!client.destroy_cursor(cursor));
+        assert_eq!(client.globals.windows[window].cursor, CursorIcon::Default);
+        let applied = client.globals.seats[0]
+            .pointer
+            .as_ref()
+            .and_then(|pointer| pointer.cursor.as_ref())
+            .and_then(|state| state.applied);
+        assert_eq!(applied, None);
+
+        assert!(
+            client
+                .set_cursor_pixels(cursor, &[0; 4], 2, 2, (0, 0))
+                .is_err()
+        );
+        let drag = client.start_drag(window, HashMap::new(), DndAction::Copy, Some(cursor));
+        assert!(drag.is_err());
+    }
+
+    #[test]
+    fn destroy_cursor_releases_every_object() {
+        let mut client = inert_client();
+        let window = client.create_window("test", "test").unwrap();
+        let cursor = client.create_cursor(&[0; 4], 2, 2, (1, 1)).unwrap();
+        // the first buffer is still waiting for its release when the second one replaces it
+        client
+            .set_cursor_pixels(cursor, &[0; 9], 3, 3, (1, 1))
+            .unwrap();
+
+        let stats = client.object_stats();
+        assert_eq!(stats.buffers.live(), 3);
+        assert_eq!(stats.surfaces.live(), 2);
+
+        assert!(client.destroy_cursor(cursor));
+        assert!(client.destroy_window(window));
+        let stats = client.object_stats();
+        assert_eq!(stats.live(), 0);
+        assert_eq!(stats.surfaces.created, 2);
+        assert_eq!(stats.pools.created, 3);
+    }
+
+    #[test]
+    fn resizing_a_destroyed_window_is_an_error() {
+        let mut client = inert_client();
+        let idx = client.create_window("test", "test").unwrap();
+        client.destroy_window(idx);
+
+        let qhandle = client.queue.handle();
+        assert!(client.globals.resize_buffer(&qhandle, idx).is_err());
+        assert!(client.globals.resize_buffer(&qhandle, idx + 1).is_err());
+        assert_eq!(client.object_stats().live(), 0);
+    }
+
+    #[test]
+    fn fractional_physical_size_rounds_half_away_from_zero() {
+        let mut client = inert_client();
+        let idx = client.create_window("test", "test").unwrap();
+        let window = &mut client.globals.windows[idx];
+
+        // 1.25
+        window.fractional_scale = Some(150);
+        let sizes: Vec<i32> = (1..=4)
+            .map(|width| {
+                window.width = width;
+                window.physical_size().0
+            })
+            .collect();
+        // 1.25, 2.5, 3.75 and 5 pixels
+        assert_eq!(sizes, [1, 3, 4, 5]);
+
+        // 1.5
+        window.fractional_scale = Some(180);
+        window.width = 1;
+        window.height = 3;
+        // 1.5 and 4.5 pixels
+        assert_eq!(window.physical_size(), (2, 5));
+
+        window.fractional_scale = None;
+        window.scale = 2;
+        assert_eq!(window.physical_size(), (2, 6));
     }
 }
@@ -0,0 +1,53 @@
+use super::damage::Rect;
+use wayland_protocols::wp::pointer_constraints::zv1::client::{
+    zwp_confined_pointer_v1, zwp_locked_pointer_v1,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintKind {
+    /// The pointer stays where it is, only relative motion is reported.
+    Lock,
+    /// The pointer moves freely but can't leave the region.
+    Confine,
+}
+
+/// Constraint a window asks for while it has pointer focus. `region` is in logical surface
+/// coordinates, `None` means the whole surface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PointerConstraint {
+    pub kind: ConstraintKind,
+    pub region: Option<Rect>,
+}
+
+/// Identifies the seat and window a locked or confined pointer object belongs to.
+#[derive(Debug, Clone, Copy)]
+pub struct ConstraintData {
+    pub seat: u32,
+    pub window: usize,
+}
+
+#[derive(Debug)]
+pub enum ConstraintObject {
+    Locked(zwp_locked_pointer_v1::ZwpLockedPointerV1),
+    Confined(zwp_confined_pointer_v1::ZwpConfinedPointerV1),
+}
+
+/// A constraint created on the compositor for one window. It is persistent, the compositor
+/// activates it again every time the pointer comes back to the window.
+#[derive(Debug)]
+pub struct AppliedConstraint {
+    pub window: usize,
+    pub constraint: PointerConstraint,
+    pub object: ConstraintObject,
+    /// Whether the compositor currently enforces the constraint.
+    pub active: bool,
+}
+
+impl AppliedConstraint {
+    pub fn destroy(&self) {
+        match &self.object {
+            ConstraintObject::Locked(locked) => locked.destroy(),
+            ConstraintObject::Confined(confined) => confined.destroy(),
+        }
+    }
+}
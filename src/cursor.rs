@@ -0,0 +1,441 @@
+use super::client::{ObjectStats, State};
+use super::error::{ClientError, ClientErrorKind};
+use std::env;
+use std::io::Write;
+use std::os::fd::AsFd;
+use std::time::{Duration, Instant};
+use wayland_client::protocol::{wl_buffer, wl_shm, wl_surface};
+use wayland_client::{Connection, Proxy, QueueHandle};
+use wayland_cursor::CursorTheme;
+use wayland_protocols::wp::cursor_shape::v1::client::wp_cursor_shape_device_v1::Shape;
+
+const DEFAULT_THEME: &str = "default";
+const DEFAULT_SIZE: u32 = 24;
+
+/// Named cursor icons, following the CSS cursor names.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CursorIcon {
+    #[default]
+    Default,
+    ContextMenu,
+    Help,
+    Pointer,
+    Progress,
+    Wait,
+    Cell,
+    Crosshair,
+    Text,
+    VerticalText,
+    Alias,
+    Copy,
+    Move,
+    NoDrop,
+    NotAllowed,
+    Grab,
+    Grabbing,
+    EResize,
+    NResize,
+    NeResize,
+    NwResize,
+    SResize,
+    SeResize,
+    SwResize,
+    WResize,
+    EwResize,
+    NsResize,
+    NeswResize,
+    NwseResize,
+    ColResize,
+    RowResize,
+    AllScroll,
+    ZoomIn,
+    ZoomOut,
+    /// No cursor at all while the pointer is over the window.
+    Hidden,
+    /// Application provided image, the index returned by `Client::create_cursor`.
+    Custom(usize),
+}
+
+impl CursorIcon {
+    /// Xcursor names to look up in the theme, in order of preference. Older themes only ship
+    /// the legacy X11 names.
+    pub fn names(&self) -> &'static [&'static str] {
+        match self {
+            CursorIcon::Default => &["default", "left_ptr"],
+            CursorIcon::ContextMenu => &["context-menu"],
+            CursorIcon::Help => &["help", "question_arrow"],
+            CursorIcon::Pointer => &["pointer", "hand2", "hand1"],
+            CursorIcon::Progress => &["progress", "left_ptr_watch"],
+            CursorIcon::Wait => &["wait", "watch"],
+            CursorIcon::Cell => &["cell", "plus"],
+            CursorIcon::Crosshair => &["crosshair", "cross"],
+            CursorIcon::Text => &["text", "xterm"],
+            CursorIcon::VerticalText => &["vertical-text"],
+            CursorIcon::Alias => &["alias", "link"],
+            CursorIcon::Copy => &["copy"],
+            CursorIcon::Move => &["move", "fleur"],
+            CursorIcon::NoDrop => &["no-drop", "circle"],
+            CursorIcon::NotAllowed => &["not-allowed", "crossed_circle"],
+            CursorIcon::Grab => &["grab", "openhand", "hand1"],
+            CursorIcon::Grabbing => &["grabbing", "closedhand"],
+            CursorIcon::EResize => &["e-resize", "right_side"],
+            CursorIcon::NResize => &["n-resize", "top_side"],
+            CursorIcon::NeResize => &["ne-resize", "top_right_corner"],
+            CursorIcon::NwResize => &["nw-resize", "top_left_corner"],
+            CursorIcon::SResize => &["s-resize", "bottom_side"],
+            CursorIcon::SeResize => &["se-resize", "bottom_right_corner"],
+            CursorIcon::SwResize => &["sw-resize", "bottom_left_corner"],
+            CursorIcon::WResize => &["w-resize", "left_side"],
+            CursorIcon::EwResize => &["ew-resize", "sb_h_double_arrow"],
+            CursorIcon::NsResize => &["ns-resize", "sb_v_double_arrow"],
+            CursorIcon::NeswResize => &["nesw-resize", "fd_double_arrow"],
+            CursorIcon::NwseResize => &["nwse-resize", "bd_double_arrow"],
+            CursorIcon::ColResize => &["col-resize", "sb_h_double_arrow"],
+            CursorIcon::RowResize => &["row-resize", "sb_v_double_arrow"],
+            CursorIcon::AllScroll => &["all-scroll", "fleur"],
+            CursorIcon::ZoomIn => &["zoom-in"],
+            CursorIcon::ZoomOut => &["zoom-out"],
+            CursorIcon::Hidden | CursorIcon::Custom(_) => &[],
+        }
+    }
+
+    /// Matching `wp_cursor_shape_device_v1` shape, `None` for icons only a theme can show.
+    pub fn shape(&self) -> Option<Shape> {
+        match self {
+            CursorIcon::Default => Some(Shape::Default),
+            CursorIcon::ContextMenu => Some(Shape::ContextMenu),
+            CursorIcon::Help => Some(Shape::Help),
+            CursorIcon::Pointer => Some(Shape::Pointer),
+            CursorIcon::Progress => Some(Shape::Progress),
+            CursorIcon::Wait => Some(Shape::Wait),
+            CursorIcon::Cell => Some(Shape::Cell),
+            CursorIcon::Crosshair => Some(Shape::Crosshair),
+            CursorIcon::Text => Some(Shape::Text),
+            CursorIcon::VerticalText => Some(Shape::VerticalText),
+            CursorIcon::Alias => Some(Shape::Alias),
+            CursorIcon::Copy => Some(Shape::Copy),
+            CursorIcon::Move => Some(Shape::Move),
+            CursorIcon::NoDrop => Some(Shape::NoDrop),
+            CursorIcon::NotAllowed => Some(Shape::NotAllowed),
+            CursorIcon::Grab => Some(Shape::Grab),
+            CursorIcon::Grabbing => Some(Shape::Grabbing),
+            CursorIcon::EResize => Some(Shape::EResize),
+            CursorIcon::NResize => Some(Shape::NResize),
+            CursorIcon::NeResize => Some(Shape::NeResize),
+            CursorIcon::NwResize => Some(Shape::NwResize),
+            CursorIcon::SResize => Some(Shape::SResize),
+            CursorIcon::SeResize => Some(Shape::SeResize),
+            CursorIcon::SwResize => Some(Shape::SwResize),
+            CursorIcon::WResize => Some(Shape::WResize),
+            CursorIcon::EwResize => Some(Shape::EwResize),
+            CursorIcon::NsResize => Some(Shape::NsResize),
+            CursorIcon::NeswResize => Some(Shape::NeswResize),
+            CursorIcon::NwseResize => Some(Shape::NwseResize),
+            CursorIcon::ColResize => Some(Shape::ColResize),
+            CursorIcon::RowResize => Some(Shape::RowResize),
+            CursorIcon::AllScroll => Some(Shape::AllScroll),
+            CursorIcon::ZoomIn => Some(Shape::ZoomIn),
+            CursorIcon::ZoomOut => Some(Shape::ZoomOut),
+            CursorIcon::Hidden | CursorIcon::Custom(_) => None,
+        }
+    }
+}
+
+/// One frame of a themed cursor, ready to be attached.
+#[derive(Debug)]
+pub struct CursorImage {
+    pub buffer: wl_buffer::WlBuffer,
+    pub width: i32,
+    pub height: i32,
+    pub hotspot_x: i32,
+    pub hotspot_y: i32,
+    /// How long the frame stays up, `None` for cursors that aren't animated.
+    pub duration: Option<Duration>,
+}
+
+/// The cursor theme from `XCURSOR_THEME`/`XCURSOR_SIZE`, loaded once per buffer scale in use.
+#[derive(Debug)]
+pub struct CursorThemes {
+    pub name: String,
+    pub size: u32,
+    pub themes: Vec<(i32, CursorTheme)>,
+}
+
+impl CursorThemes {
+    pub fn from_env() -> Self {
+        CursorThemes {
+            name: env::var("XCURSOR_THEME")
+                .ok()
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| DEFAULT_THEME.to_string()),
+            size: env::var("XCURSOR_SIZE")
+                .ok()
+                .and_then(|size| size.parse().ok())
+                .unwrap_or(DEFAULT_SIZE),
+            themes: Vec::new(),
+        }
+    }
+
+    /// Looks up the frame of `icon` that should be visible `elapsed` after the cursor was set.
+    pub fn image(
+        &mut self,
+        conn: &Connection,
+        shm: &wl_shm::WlShm,
+        icon: CursorIcon,
+        scale: i32,
+        elapsed: Duration,
+    ) -> Option<CursorImage> {
+        if !self
+            .themes
+            .iter()
+            .any(|(theme_scale, _)| *theme_scale == scale)
+        {
+            let theme = CursorTheme::load_from_name(
+                conn,
+                shm.clone(),
+                &self.name,
+                self.size * scale as u32,
+            )
+            .ok()?;
+            self.themes.push((scale, theme));
+        }
+        let (_, theme) = self
+            .themes
+            .iter_mut()
+            .find(|(theme_scale, _)| *theme_scale == scale)?;
+
+        let name = icon
+            .names()
+            .iter()
+            .find(|name| theme.get_cursor(name).is_some())?;
+        let cursor = theme.get_cursor(name)?;
+
+        let frame = cursor.frame_and_duration(elapsed.as_millis() as u32);
+        let image = &cursor[frame.frame_index];
+        let (width, height) = image.dimensions();
+        let (hotspot_x, hotspot_y) = image.hotspot();
+
+        Some(CursorImage {
+            buffer: (**image).clone(),
+            width: width as i32,
+            height: height as i32,
+            hotspot_x: hotspot_x as i32,
+            hotspot_y: hotspot_y as i32,
+            duration: (cursor.image_count() > 1)
+                .then(|| Duration::from_millis(frame.frame_duration as u64)),
+        })
+    }
+}
+
+/// What the pointer currently shows, so it is only updated when something changed.
+#[derive(Debug)]
+pub struct CursorState {
+    pub surface: wl_surface::WlSurface,
+
+    /// Window, icon and scale the cursor was last set for.
+    pub applied: Option<(usize, CursorIcon, i32)>,
+    pub started: Instant,
+    /// When the next animation frame is due.
+    pub next_frame: Option<Instant>,
+}
+
+impl CursorState {
+    pub fn new(surface: wl_surface::WlSurface) -> Self {
+        CursorState {
+            surface,
+            applied: None,
+            started: Instant::now(),
+            next_frame: None,
+        }
+    }
+
+    /// Attaches `image` to the cursor surface at `scale`. Returns `false` when the image can't be
+    /// shown at that scale, because the protocol requires buffer sizes divisible by the scale.
+    pub fn attach(&self, image: &CursorImage, scale: i32) -> bool {
+        if image.width % scale != 0 || image.height % scale != 0 {
+            return false;
+        }
+
+        if self.surface.version() >= 3 {
+            self.surface.set_buffer_scale(scale);
+        }
+        self.surface.attach(Some(&image.buffer), 0, 0);
+        self.surface.damage_buffer(0, 0, image.width, image.height);
+        self.surface.commit();
+        true
+    }
+}
+
+/// Identifies the custom cursor a `wl_buffer` belongs to.
+#[derive(Debug, Clone, Copy)]
+pub struct CustomCursorBuffer {
+    pub cursor: usize,
+}
+
+/// What a custom cursor's surface is used as. A surface keeps the first role it is given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorRole {
+    Pointer,
+    DragIcon,
+}
+
+/// A cursor drawn from application pixels, with its own surface and shm buffers.
+#[derive(Debug)]
+pub struct CustomCursor {
+    pub surface: wl_surface::WlSurface,
+    /// Attached buffers with whether the compositor released them, the last one is current.
+    /// Replaced buffers are destroyed once released.
+    pub buffers: Vec<(wl_buffer::WlBuffer, bool)>,
+
+    pub width: i32,
+    pub height: i32,
+    pub hotspot_x: i32,
+    pub hotspot_y: i32,
+    /// Set the first time the cursor is shown, as a pointer cursor or as a drag icon.
+    pub role: Option<CursorRole>,
+    pub destroyed: bool,
+
+    /// Protocol objects the cursor created and destroyed so far.
+    pub objects: ObjectStats,
+}
+
+impl CustomCursor {
+    pub fn new(surface: wl_surface::WlSurface) -> Self {
+        let mut objects = ObjectStats::default();
+        objects.surfaces.created += 1;
+        CustomCursor {
+            surface,
+            buffers: Vec::new(),
+            width: 0,
+            height: 0,
+            hotspot_x: 0,
+            hotspot_y: 0,
+            role: None,
+            destroyed: false,
+            objects,
+        }
+    }
+
+    /// Uploads `pixels` (premultiplied ARGB8888, row by row) into a new buffer and commits it to
+    /// the cursor surface.
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_pixels(
+        &mut self,
+        shm: &wl_shm::WlShm,
+        qhandle: &QueueHandle<State>,
+        id: usize,
+        pixels: &[u32],
+        width: i32,
+        height: i32,
+        hotspot: (i32, i32),
+    ) -> Result<(), ClientError> {
+        // the pool size is an i32, the whole image has to fit in it
+        let size = (width as usize)
+            .checked_mul(height as usize)
+            .filter(|&count| width > 0 && height > 0 && count == pixels.len())
+            .and_then(|count| count.checked_mul(4))
+            .and_then(|bytes| i32::try_from(bytes).ok());
+        let Some(size) = size else {
+            return Err(ClientError::Initialization {
+                kind: ClientErrorKind::Pixel,
+                message: format!(
+                    "Cursor pixels don't match the size ({} pixels for {width}x{height})",
+                    pixels.len()
+                ),
+            });
+        };
+
+        let stride = width * 4;
+        let bytes: Vec<u8> = pixels
+            .iter()
+            .flat_map(|pixel| pixel.to_le_bytes())
+            .collect();
+
+        let mut file = tempfile::tempfile()?;
+        file.set_len(size as u64)?;
+        file.write_all(&bytes)?;
+
+        // the buffer keeps the pool memory alive, neither the pool nor the file are needed after
+        let pool = shm.create_pool(file.as_fd(), size, qhandle, ());
+        let buffer = pool.create_buffer(
+            0,
+            width,
+            height,
+            stride,
+            wl_shm::Format::Argb8888,
+            qhandle,
+            CustomCursorBuffer { cursor: id },
+        );
+        pool.destroy();
+        self.objects.pools.created += 1;
+        self.objects.pools.destroyed += 1;
+        self.objects.buffers.created += 1;
+
+        self.surface.attach(Some(&buffer), 0, 0);
+        self.surface.damage_buffer(0, 0, width, height);
+        self.surface.commit();
+
+        // older buffers still in use go away on their release
+        let objects = &mut self.objects;
+        self.buffers.retain(|(buffer, released)| {
+            if *released {
+                buffer.destroy();
+                objects.buffers.destroyed += 1;
+            }
+            !released
+        });
+        self.buffers.push((buffer, false));
+
+        self.width = width;
+        self.height = height;
+        self.hotspot_x = hotspot.0;
+        self.hotspot_y = hotspot.1;
+        Ok(())
+    }
+
+    /// Moves the surface so the hotspot lands on the pointer. Used for drag icons, whose role has
+    /// no hotspot of its own.
+    pub fn offset_to_hotspot(&mut self) {
+        self.offset(-self.hotspot_x, -self.hotspot_y);
+    }
+
+    /// Moves the surface by `(x, y)` from where it is now.
+    pub fn offset(&mut self, x: i32, y: i32) {
+        let Some((buffer, released)) = self.buffers.last_mut() else {
+            return;
+        };
+        if self.surface.version() >= 5 {
+            self.surface.offset(x, y);
+        } else {
+            self.surface.attach(Some(buffer), x, y);
+            *released = false;
+        }
+        self.surface.commit();
+    }
+
+    /// Destroys the surface and every buffer, released or not.
+    pub fn destroy(&mut self) {
+        for (buffer, _) in self.buffers.drain(..) {
+            buffer.destroy();
+            self.objects.buffers.destroyed += 1;
+        }
+        self.surface.destroy();
+        self.objects.surfaces.destroyed += 1;
+        self.destroyed = true;
+    }
+
+    /// Handles `wl_buffer.release`, destroying the buffer unless it is still the current one.
+    pub fn release_buffer(&mut self, buffer: &wl_buffer::WlBuffer) {
+        let current = self.buffers.len().saturating_sub(1);
+        let Some(pos) = self.buffers.iter().position(|(other, _)| other == buffer) else {
+            return;
+        };
+        if pos == current {
+            self.buffers[pos].1 = true;
+        } else {
+            let (buffer, _) = self.buffers.remove(pos);
+            buffer.destroy();
+            self.objects.buffers.destroyed += 1;
+        }
+    }
+}
@@ -0,0 +1,178 @@
+/// Above this many rectangles damage is collapsed into its bounding box, compositors don't gain
+/// anything from very fragmented regions.
+const MAX_DAMAGE_RECTS: usize = 16;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width <= 0 || self.height <= 0
+    }
+
+    /// Returns the part of `self` that lies inside `bounds` (possibly empty).
+    pub fn clip(&self, bounds: &Rect) -> Rect {
+        let x0 = self.x.max(bounds.x);
+        let y0 = self.y.max(bounds.y);
+        let x1 = (self.x + self.width).min(bounds.x + bounds.width);
+        let y1 = (self.y + self.height).min(bounds.y + bounds.height);
+        Rect::new(x0, y0, (x1 - x0).max(0), (y1 - y0).max(0))
+    }
+
+    /// True when the rectangles have some area in common.
+    pub fn overlaps(&self, other: &Rect) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+
+    /// True when the rectangles sit side by side along a whole edge, so their union is exactly
+    /// the two of them.
+    pub fn shares_edge(&self, other: &Rect) -> bool {
+        let side_by_side = self.y == other.y
+            && self.height == other.height
+            && (self.x + self.width == other.x || other.x + other.width == self.x);
+        let stacked = self.x == other.x
+            && self.width == other.width
+            && (self.y + self.height == other.y || other.y + other.height == self.y);
+        side_by_side || stacked
+    }
+
+    /// True when the rectangles overlap or share a whole edge. Merging along an edge is exact,
+    /// merging overlapping ones may take in some undamaged area around the overlap. Rectangles
+    /// that only meet at a corner, or along part of an edge, are kept apart.
+    pub fn can_merge(&self, other: &Rect) -> bool {
+        self.overlaps(other) || self.shares_edge(other)
+    }
+
+    pub fn union(&self, other: &Rect) -> Rect {
+        let x0 = self.x.min(other.x);
+        let y0 = self.y.min(other.y);
+        let x1 = (self.x + self.width).max(other.x + other.width);
+        let y1 = (self.y + self.height).max(other.y + other.height);
+        Rect::new(x0, y0, x1 - x0, y1 - y0)
+    }
+}
+
+/// Clips every rectangle to `bounds`, drops empty ones and merges the ones that overlap or share
+/// a whole edge.
+pub fn normalize(rects: &[Rect], bounds: &Rect) -> Vec<Rect> {
+    let mut merged: Vec<Rect> = Vec::with_capacity(rects.len());
+
+    for rect in rects {
+        let mut rect = rect.clip(bounds);
+        if rect.is_empty() {
+            continue;
+        }
+
+        // merging can make the grown rect reach ones that were already checked, so keep going
+        // until nothing else merges into it
+        while let Some(pos) = merged.iter().position(|other| other.can_merge(&rect)) {
+            rect = rect.union(&merged.swap_remove(pos));
+        }
+        merged.push(rect);
+    }
+
+    if merged.len() > MAX_DAMAGE_RECTS {
+        let bounding = merged.iter().skip(1).fold(merged[0], |acc, r| acc.union(r));
+        return vec![bounding];
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: Rect = Rect {
+        x: 0,
+        y: 0,
+        width: 100,
+        height: 100,
+    };
+
+    #[test]
+    fn overlapping_rects_merge() {
+        let rects = [Rect::new(0, 0, 20, 20), Rect::new(10, 10, 20, 20)];
+        assert_eq!(normalize(&rects, &BOUNDS), vec![Rect::new(0, 0, 30, 30)]);
+    }
+
+    #[test]
+    fn rects_sharing_a_whole_edge_merge() {
+        let side_by_side = [Rect::new(0, 10, 20, 30), Rect::new(20, 10, 5, 30)];
+        assert_eq!(
+            normalize(&side_by_side, &BOUNDS),
+            vec![Rect::new(0, 10, 25, 30)]
+        );
+
+        let stacked = [Rect::new(10, 40, 30, 10), Rect::new(10, 0, 30, 40)];
+        assert_eq!(normalize(&stacked, &BOUNDS), vec![Rect::new(10, 0, 30, 50)]);
+    }
+
+    #[test]
+    fn rects_sharing_part_of_an_edge_stay_apart() {
+        let rects = [Rect::new(0, 0, 20, 20), Rect::new(20, 10, 20, 20)];
+        assert_eq!(normalize(&rects, &BOUNDS), rects.to_vec());
+    }
+
+    #[test]
+    fn rects_meeting_at_a_corner_stay_apart() {
+        let rects = [Rect::new(0, 0, 20, 20), Rect::new(20, 20, 20, 20)];
+        assert_eq!(normalize(&rects, &BOUNDS), rects.to_vec());
+    }
+
+    #[test]
+    fn merging_picks_up_rects_checked_before() {
+        // the third rect bridges the first two, which don't merge on their own
+        let rects = [
+            Rect::new(0, 0, 10, 10),
+            Rect::new(20, 0, 10, 10),
+            Rect::new(5, 0, 20, 10),
+        ];
+        assert_eq!(normalize(&rects, &BOUNDS), vec![Rect::new(0, 0, 30, 10)]);
+    }
+
+    #[test]
+    fn rects_are_clipped_to_bounds() {
+        let rects = [Rect::new(-10, 90, 30, 30)];
+        assert_eq!(normalize(&rects, &BOUNDS), vec![Rect::new(0, 90, 20, 10)]);
+    }
+
+    #[test]
+    fn empty_rects_are_dropped() {
+        let rects = [
+            Rect::new(10, 10, 0, 10),
+            Rect::new(10, 10, 10, -5),
+            Rect::new(200, 200, 10, 10),
+        ];
+        assert!(normalize(&rects, &BOUNDS).is_empty());
+    }
+
+    #[test]
+    fn too_many_rects_collapse_into_their_bounding_box() {
+        let rects: Vec<Rect> = (0..MAX_DAMAGE_RECTS as i32 + 1)
+            .map(|i| Rect::new(i * 5, i * 5, 2, 2))
+            .collect();
+        let last = MAX_DAMAGE_RECTS as i32 * 5;
+        assert_eq!(
+            normalize(&rects, &BOUNDS),
+            vec![Rect::new(0, 0, last + 2, last + 2)]
+        );
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, ErrorKind, PipeReader, Read, Write};
+use std::os::fd::{AsFd, BorrowedFd, OwnedFd};
+use wayland_client::Proxy;
+use wayland_client::protocol::{
+    wl_data_device, wl_data_device_manager::DndAction, wl_data_offer, wl_data_source,
+};
+
+/// Text types that carry the same UTF-8 text, tried in order when one of them is asked for and
+/// the offer doesn't list it.
+const TEXT_MIME_TYPES: &[&str] = &[
+    "text/plain;charset=utf-8",
+    "UTF8_STRING",
+    "text/plain",
+    "STRING",
+    "TEXT",
+];
+
+/// Picks the type to ask the source for: `mime_type` when offered, else an equivalent text type.
+pub fn negotiate_mime_type<'a>(offered: &'a [String], mime_type: &str) -> Option<&'a str> {
+    if let Some(exact) = offered.iter().find(|offered| *offered == mime_type) {
+        return Some(exact);
+    }
+    if !TEXT_MIME_TYPES.contains(&mime_type) {
+        return None;
+    }
+    TEXT_MIME_TYPES
+        .iter()
+        .find_map(|text| offered.iter().find(|offered| offered == text))
+        .map(String::as_str)
+}
+
+/// Starts writing the data for `mime_type` into `fd`. `None` when the type isn't offered or the
+/// fd can't be made non-blocking, dropping `fd` then ends the transfer for the reader.
+pub fn send_data(
+    data: &HashMap<String, Vec<u8>>,
+    mime_type: &str,
+    fd: OwnedFd,
+) -> Option<DataWrite> {
+    let data = data.get(mime_type)?;
+    DataWrite::new(data.clone(), fd).ok()
+}
+
+/// Data another client (or this one) offers, with the MIME types it can be converted to.
+#[derive(Debug)]
+pub struct DataOffer {
+    pub wl_data_offer: wl_data_offer::WlDataOffer,
+    pub mime_types: Vec<String>,
+    /// Actions the source of a drag supports.
+    pub source_actions: DndAction,
+    /// Action the compositor picked for a drag from the source and target actions.
+    pub action: DndAction,
+}
+
+impl DataOffer {
+    pub fn new(wl_data_offer: wl_data_offer::WlDataOffer) -> Self {
+        DataOffer {
+            wl_data_offer,
+            mime_types: Vec::new(),
+            source_actions: DndAction::empty(),
+            action: DndAction::empty(),
+        }
+    }
+
+    /// Asks the source to write the data as `mime_type` into a pipe, the returned reader picks it
+    /// up without blocking. The request goes out with the next flush.
+    pub fn receive(&self, mime_type: &str) -> io::Result<DataRead> {
+        DataRead::new(mime_type, |fd| {
+            self.wl_data_offer.receive(mime_type.to_string(), fd)
+        })
+    }
+
+    pub fn destroy(&self) {
+        self.wl_data_offer.destroy();
+    }
+}
+
+/// Transfer started by `Client::read_clipboard` or `Client::read_primary_selection`. Poll it from
+/// the event loop, or wait on its fd.
+#[derive(Debug)]
+pub struct DataRead {
+    /// Type the data was asked for in, after negotiation.
+    pub mime_type: String,
+    pub reader: PipeReader,
+    pub data: Vec<u8>,
+}
+
+impl DataRead {
+    /// Creates the pipe and hands its write end to `request`, which passes it to the source.
+    pub fn new(mime_type: &str, request: impl FnOnce(BorrowedFd)) -> io::Result<Self> {
+        let (reader, writer) = io::pipe()?;
+        request(writer.as_fd());
+        // the source holds the only write end now, the read ends once it closes it
+        drop(writer);
+        rustix::io::ioctl_fionbio(&reader, true)?;
+
+        Ok(DataRead {
+            mime_type: mime_type.to_string(),
+            reader,
+            data: Vec::new(),
+        })
+    }
+
+    /// Reads whatever is available. Returns the data once the source finished writing, `None`
+    /// while it is still coming.
+    pub fn poll(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut chunk = [0; 4096];
+        loop {
+            match self.reader.read(&mut chunk) {
+                Ok(0) => return Ok(Some(std::mem::take(&mut self.data))),
+                Ok(read) => self.data.extend_from_slice(&chunk[..read]),
+                Err(err) if err.kind() == ErrorKind::WouldBlock => return Ok(None),
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+impl AsFd for DataRead {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.reader.as_fd()
+    }
+}
+
+/// Transfer of this client's data to a reader, moved along by the event loop so a slow reader
+/// can't stall it.
+#[derive(Debug)]
+pub struct DataWrite {
+    pub writer: File,
+    pub data: Vec<u8>,
+    /// Bytes of `data` the reader already took.
+    pub written: usize,
+}
+
+impl DataWrite {
+    pub fn new(data: Vec<u8>, fd: OwnedFd) -> io::Result<Self> {
+        rustix::io::ioctl_fionbio(&fd, true)?;
+        Ok(DataWrite {
+            writer: File::from(fd),
+            data,
+            written: 0,
+        })
+    }
+
+    /// Writes as much as the reader takes without blocking. Returns true once everything is
+    /// written, dropping the write then closes the fd and ends the transfer.
+    pub fn poll(&mut self) -> io::Result<bool> {
+        while self.written < self.data.len() {
+            match self.writer.write(&self.data[self.written..]) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(written) => self.written += written,
+                Err(err) if err.kind() == ErrorKind::WouldBlock => return Ok(false),
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl AsFd for DataWrite {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.writer.as_fd()
+    }
+}
+
+/// Data this client offers, kept until the compositor cancels the source.
+#[derive(Debug)]
+pub struct DataSource {
+    pub wl_data_source: wl_data_source::WlDataSource,
+    /// Contents for every offered MIME type.
+    pub data: HashMap<String, Vec<u8>>,
+}
+
+impl DataSource {
+    pub fn send(&self, mime_type: &str, fd: OwnedFd) -> Option<DataWrite> {
+        send_data(&self.data, mime_type, fd)
+    }
+}
+
+/// A drag over one of our windows, started by this or another client.
+#[derive(Debug)]
+pub struct DragOffer {
+    pub offer: DataOffer,
+    pub window: usize,
+    /// Serial of `wl_data_device.enter`, needed to accept the offer.
+    pub serial: u32,
+    /// Type passed to `Client::accept_drag`, `None` while the drop would be refused.
+    pub accepted: Option<String>,
+    /// Set once dropped, the offer stays around until `Client::finish_drop`.
+    pub dropped: bool,
+}
+
+impl DragOffer {
+    /// Tells the source the drop was handled, then destroys the offer. A drop that wasn't
+    /// accepted, or whose action is still `Ask`, can't be finished and is just dropped.
+    pub fn finish(&self) {
+        if self.dropped
+            && self.accepted.is_some()
+            && !self.offer.action.is_empty()
+            && self.offer.action != DndAction::Ask
+            && self.offer.wl_data_offer.version() >= 3
+        {
+            self.offer.wl_data_offer.finish();
+        }
+        self.offer.destroy();
+    }
+}
+
+/// Data dragged from one of our windows with `Client::start_drag`.
+#[derive(Debug)]
+pub struct DragSource {
+    pub source: DataSource,
+    /// Action the compositor picked, reported back when the drop finishes.
+    pub action: DndAction,
+}
+
+/// Clipboard and drag and drop of one seat.
+#[derive(Debug)]
+pub struct DataDevice {
+    pub wl_data_device: wl_data_device::WlDataDevice,
+
+    /// Offers introduced by `wl_data_device.data_offer` that weren't assigned a role yet.
+    pub offers: Vec<DataOffer>,
+    /// Current clipboard contents, `None` when the clipboard is empty.
+    pub selection: Option<DataOffer>,
+    /// Drag over one of our windows, or dropped on it and not finished yet.
+    pub drag: Option<DragOffer>,
+}
+
+impl DataDevice {
+    pub fn new(wl_data_device: wl_data_device::WlDataDevice) -> Self {
+        DataDevice {
+            wl_data_device,
+            offers: Vec::new(),
+            selection: None,
+            drag: None,
+        }
+    }
+
+    /// Removes a pending offer to give it a role.
+    pub fn take_offer(&mut self, wl_data_offer: &wl_data_offer::WlDataOffer) -> Option<DataOffer> {
+        let pos = self
+            .offers
+            .iter()
+            .position(|offer| offer.wl_data_offer == *wl_data_offer)?;
+        Some(self.offers.remove(pos))
+    }
+
+    /// Offer with the given protocol object, pending or current.
+    pub fn offer_mut(
+        &mut self,
+        wl_data_offer: &wl_data_offer::WlDataOffer,
+    ) -> Option<&mut DataOffer> {
+        self.offers
+            .iter_mut()
+            .chain(self.selection.as_mut())
+            .chain(self.drag.as_mut().map(|drag| &mut drag.offer))
+            .find(|offer| offer.wl_data_offer == *wl_data_offer)
+    }
+
+    pub fn destroy(&self) {
+        for offer in self
+            .offers
+            .iter()
+            .chain(self.selection.as_ref())
+            .chain(self.drag.as_ref().map(|drag| &drag.offer))
+        {
+            offer.destroy();
+        }
+        if self.wl_data_device.version() >= 2 {
+            self.wl_data_device.release();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offered(types: &[&str]) -> Vec<String> {
+        types.iter().map(|mime| mime.to_string()).collect()
+    }
+
+    #[test]
+    fn exact_type_is_preferred() {
+        let types = offered(&["text/plain", "text/html", "UTF8_STRING"]);
+        assert_eq!(
+            negotiate_mime_type(&types, "UTF8_STRING"),
+            Some("UTF8_STRING")
+        );
+        assert_eq!(negotiate_mime_type(&types, "text/html"), Some("text/html"));
+    }
+
+    #[test]
+    fn text_falls_back_to_an_equivalent_type() {
+        let types = offered(&["STRING", "text/plain", "image/png"]);
+        // the utf-8 type is tried first, then the list goes down in order
+        assert_eq!(
+            negotiate_mime_type(&types, "text/plain;charset=utf-8"),
+            Some("text/plain")
+        );
+        assert_eq!(negotiate_mime_type(&types, "TEXT"), Some("text/plain"));
+    }
+
+    #[test]
+    fn other_types_do_not_fall_back() {
+        let types = offered(&["image/png", "text/plain"]);
+        assert_eq!(negotiate_mime_type(&types, "image/jpeg"), None);
+        assert_eq!(negotiate_mime_type(&offered(&["image/png"]), "TEXT"), None);
+    }
+
+    #[test]
+    fn write_moves_along_as_the_reader_drains_the_pipe() {
+        let (mut reader, writer) = io::pipe().unwrap();
+        // larger than a pipe buffer, so it can't go out in one go
+        let data: Vec<u8> = (0..1 << 20).map(|i| i as u8).collect();
+        let mut write = DataWrite::new(data.clone(), OwnedFd::from(writer)).unwrap();
+
+        let mut received = Vec::new();
+        let mut chunk = vec![0; 1 << 16];
+        while !write.poll().unwrap() {
+            let read = reader.read(&mut chunk).unwrap();
+            received.extend_from_slice(&chunk[..read]);
+        }
+        drop(write);
+        reader.read_to_end(&mut received).unwrap();
+
+        assert_eq!(received, data);
+    }
+
+    #[test]
+    fn write_fails_once_the_reader_is_gone() {
+        let (reader, writer) = io::pipe().unwrap();
+        let mut write = DataWrite::new(vec![1; 16], OwnedFd::from(writer)).unwrap();
+        drop(reader);
+        assert!(write.poll().is_err());
+    }
+
+    #[test]
+    fn nothing_is_sent_for_a_type_not_offered() {
+        let (mut reader, writer) = io::pipe().unwrap();
+        let data = HashMap::from([("text/plain".to_string(), b"hi".to_vec())]);
+        assert!(send_data(&data, "image/png", OwnedFd::from(writer)).is_none());
+
+        let mut received = Vec::new();
+        reader.read_to_end(&mut received).unwrap();
+        assert!(received.is_empty());
+    }
+}
@@ -1,11 +1,55 @@
-use crate::client::State;
+use crate::client::{ControlFlow, FeedbackData, State};
+use crate::constraint::ConstraintData;
+use crate::cursor::CustomCursorBuffer;
+use crate::data_device::{DataDevice, DataOffer, DragOffer};
+use crate::event::Event;
+use crate::gesture::GestureEvent;
+use crate::keyboard::{RepeatInfo, Xkb};
+use crate::output::{Mode, Output};
+use crate::pointer::PointerEvent;
+use crate::primary_selection::{PrimarySelectionDevice, PrimarySelectionOffer};
+use crate::seat::Seat;
+use crate::tablet::{Tablet, TabletPad, TabletPadEvent, TabletSeat, TabletTool, TabletToolEvent};
+use crate::timing::PresentationInfo;
+use crate::touch::{TouchEvent, TouchPoint};
 use wayland_client::{
     Connection, Dispatch, Proxy, QueueHandle,
     protocol::{
-        wl_buffer, wl_callback, wl_compositor, wl_registry, wl_shm, wl_shm_pool, wl_surface,
+        wl_buffer, wl_callback, wl_compositor, wl_data_device,
+        wl_data_device_manager::{self, DndAction},
+        wl_data_offer, wl_data_source, wl_keyboard, wl_output, wl_pointer, wl_region, wl_registry,
+        wl_seat, wl_shm, wl_shm_pool, wl_surface, wl_touch,
     },
 };
+use wayland_protocols::wp::cursor_shape::v1::client::{
+    wp_cursor_shape_device_v1, wp_cursor_shape_manager_v1,
+};
+use wayland_protocols::wp::fractional_scale::v1::client::{
+    wp_fractional_scale_manager_v1, wp_fractional_scale_v1,
+};
+use wayland_protocols::wp::pointer_constraints::zv1::client::{
+    zwp_confined_pointer_v1, zwp_locked_pointer_v1, zwp_pointer_constraints_v1,
+};
+use wayland_protocols::wp::pointer_gestures::zv1::client::{
+    zwp_pointer_gesture_hold_v1, zwp_pointer_gesture_pinch_v1, zwp_pointer_gesture_swipe_v1,
+    zwp_pointer_gestures_v1,
+};
+use wayland_protocols::wp::presentation_time::client::{wp_presentation, wp_presentation_feedback};
+use wayland_protocols::wp::primary_selection::zv1::client::{
+    zwp_primary_selection_device_manager_v1, zwp_primary_selection_device_v1,
+    zwp_primary_selection_offer_v1, zwp_primary_selection_source_v1,
+};
+use wayland_protocols::wp::relative_pointer::zv1::client::{
+    zwp_relative_pointer_manager_v1, zwp_relative_pointer_v1,
+};
+use wayland_protocols::wp::tablet::zv2::client::{
+    zwp_tablet_manager_v2, zwp_tablet_pad_dial_v2, zwp_tablet_pad_group_v2, zwp_tablet_pad_ring_v2,
+    zwp_tablet_pad_strip_v2, zwp_tablet_pad_v2, zwp_tablet_seat_v2, zwp_tablet_tool_v2,
+    zwp_tablet_v2,
+};
+use wayland_protocols::wp::viewporter::client::{wp_viewport, wp_viewporter};
 use wayland_protocols::xdg::shell::client::{xdg_surface, xdg_toplevel, xdg_wm_base};
+use wayland_protocols::xdg::xdg_output::zv1::client::{zxdg_output_manager_v1, zxdg_output_v1};
 
 impl Dispatch<wl_registry::WlRegistry, ()> for State {
     fn event(
@@ -16,14 +60,12 @@ impl Dispatch<wl_registry::WlRegistry, ()> for State {
         _conn: &Connection,
         qhandle: &QueueHandle<Self>,
     ) {
-        // TODO: also handle GlobalRemove
-        if let wl_registry::Event::Global {
-            name,
-            interface,
-            version,
-        } = event
-        {
-            match &interface[..] {
+        match event {
+            wl_registry::Event::Global {
+                name,
+                interface,
+                version,
+            } => match &interface[..] {
                 "wl_compositor" => {
                     state.compositor = Some(proxy.bind::<wl_compositor::WlCompositor, _, _>(
                         name,
@@ -35,6 +77,163 @@ impl Dispatch<wl_registry::WlRegistry, ()> for State {
                 "wl_shm" => {
                     state.shm = Some(proxy.bind::<wl_shm::WlShm, _, _>(name, version, qhandle, ()));
                 }
+                "wl_output" => {
+                    let wl_output = proxy.bind::<wl_output::WlOutput, _, _>(
+                        name,
+                        version.min(4),
+                        qhandle,
+                        name,
+                    );
+                    let mut output = Output::new(name, wl_output);
+                    if let Some(manager) = &state.xdg_output_manager {
+                        output.xdg_output =
+                            Some(manager.get_xdg_output(&output.wl_output, qhandle, name));
+                    }
+                    state.outputs.push(output);
+                }
+                "wl_seat" => {
+                    let wl_seat =
+                        proxy.bind::<wl_seat::WlSeat, _, _>(name, version.min(8), qhandle, name);
+                    let mut seat = Seat::new(name, wl_seat);
+                    seat.tablet = state.tablet_manager.as_ref().map(|manager| {
+                        TabletSeat::new(manager.get_tablet_seat(&seat.wl_seat, qhandle, name))
+                    });
+                    seat.data_device = state.data_device_manager.as_ref().map(|manager| {
+                        DataDevice::new(manager.get_data_device(&seat.wl_seat, qhandle, name))
+                    });
+                    seat.primary_selection =
+                        state.primary_selection_manager.as_ref().map(|manager| {
+                            PrimarySelectionDevice::new(manager.get_device(
+                                &seat.wl_seat,
+                                qhandle,
+                                name,
+                            ))
+                        });
+                    state.seats.push(seat);
+                    state.events.push_back(Event::SeatAdded { seat: name });
+                }
+                "zxdg_output_manager_v1" => {
+                    let manager = proxy.bind::<zxdg_output_manager_v1::ZxdgOutputManagerV1, _, _>(
+                        name,
+                        version.min(3),
+                        qhandle,
+                        (),
+                    );
+                    for output in &mut state.outputs {
+                        output.xdg_output =
+                            Some(manager.get_xdg_output(&output.wl_output, qhandle, output.id));
+                    }
+                    state.xdg_output_manager = Some(manager);
+                }
+                "wp_fractional_scale_manager_v1" => {
+                    state.fractional_scale_manager = Some(proxy.bind::<
+                        wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1,
+                        _,
+                        _,
+                    >(
+                        name, version.min(1), qhandle, ()
+                    ));
+                }
+                "wp_cursor_shape_manager_v1" => {
+                    state.cursor_shape_manager = Some(
+                        proxy.bind::<wp_cursor_shape_manager_v1::WpCursorShapeManagerV1, _, _>(
+                            name,
+                            version.min(1),
+                            qhandle,
+                            (),
+                        ),
+                    );
+                }
+                "wp_viewporter" => {
+                    state.viewporter = Some(proxy.bind::<wp_viewporter::WpViewporter, _, _>(
+                        name,
+                        version.min(1),
+                        qhandle,
+                        (),
+                    ));
+                }
+                "zwp_pointer_gestures_v1" => {
+                    state.pointer_gestures = Some(
+                        proxy.bind::<zwp_pointer_gestures_v1::ZwpPointerGesturesV1, _, _>(
+                            name,
+                            version.min(3),
+                            qhandle,
+                            (),
+                        ),
+                    );
+                }
+                "zwp_pointer_constraints_v1" => {
+                    state.pointer_constraints = Some(
+                        proxy.bind::<zwp_pointer_constraints_v1::ZwpPointerConstraintsV1, _, _>(
+                            name,
+                            version.min(1),
+                            qhandle,
+                            (),
+                        ),
+                    );
+                }
+                "zwp_relative_pointer_manager_v1" => {
+                    state.relative_pointer_manager = Some(proxy.bind::<
+                        zwp_relative_pointer_manager_v1::ZwpRelativePointerManagerV1,
+                        _,
+                        _,
+                    >(
+                        name, version.min(1), qhandle, ()
+                    ));
+                }
+                "zwp_tablet_manager_v2" => {
+                    let manager = proxy.bind::<zwp_tablet_manager_v2::ZwpTabletManagerV2, _, _>(
+                        name,
+                        version.min(2),
+                        qhandle,
+                        (),
+                    );
+                    for seat in &mut state.seats {
+                        seat.tablet = Some(TabletSeat::new(manager.get_tablet_seat(
+                            &seat.wl_seat,
+                            qhandle,
+                            seat.id,
+                        )));
+                    }
+                    state.tablet_manager = Some(manager);
+                }
+                "wl_data_device_manager" => {
+                    let manager = proxy.bind::<wl_data_device_manager::WlDataDeviceManager, _, _>(
+                        name,
+                        version.min(3),
+                        qhandle,
+                        (),
+                    );
+                    for seat in &mut state.seats {
+                        seat.data_device = Some(DataDevice::new(manager.get_data_device(
+                            &seat.wl_seat,
+                            qhandle,
+                            seat.id,
+                        )));
+                    }
+                    state.data_device_manager = Some(manager);
+                }
+                "zwp_primary_selection_device_manager_v1" => {
+                    let manager = proxy.bind::<
+                        zwp_primary_selection_device_manager_v1::ZwpPrimarySelectionDeviceManagerV1,
+                        _,
+                        _,
+                    >(name, version.min(1), qhandle, ());
+                    for seat in &mut state.seats {
+                        seat.primary_selection = Some(PrimarySelectionDevice::new(
+                            manager.get_device(&seat.wl_seat, qhandle, seat.id),
+                        ));
+                    }
+                    state.primary_selection_manager = Some(manager);
+                }
+                "wp_presentation" => {
+                    state.presentation = Some(proxy.bind::<wp_presentation::WpPresentation, _, _>(
+                        name,
+                        version.min(1),
+                        qhandle,
+                        (),
+                    ));
+                }
                 "xdg_wm_base" => {
                     state.xdg_wm_base = Some(proxy.bind::<xdg_wm_base::XdgWmBase, _, _>(
                         name,
@@ -44,7 +243,51 @@ impl Dispatch<wl_registry::WlRegistry, ()> for State {
                     ));
                 }
                 _ => (),
+            },
+            wl_registry::Event::GlobalRemove { name } => {
+                if let Some(idx) = state.seats.iter().position(|seat| seat.id == name) {
+                    state.update_capabilities(qhandle, idx, wl_seat::Capability::empty());
+                    let seat = state.seats.remove(idx);
+                    if let Some(tablet_seat) = &seat.tablet {
+                        tablet_seat.destroy();
+                    }
+                    if let Some(device) = &seat.data_device {
+                        device.destroy();
+                    }
+                    if let Some(device) = &seat.primary_selection {
+                        device.destroy();
+                    }
+                    if seat.wl_seat.version() >= 5 {
+                        seat.wl_seat.release();
+                    }
+                    state.events.push_back(Event::SeatRemoved { seat: name });
+                }
+                if let Some(pos) = state.outputs.iter().position(|output| output.id == name) {
+                    let output = state.outputs.remove(pos);
+                    output.destroy();
+
+                    // compositors don't always send wl_surface.leave for an output that is gone
+                    for (idx, window) in state.windows.iter_mut().enumerate() {
+                        if let Some(pos) = window.outputs.iter().position(|other| *other == name) {
+                            window.outputs.remove(pos);
+                            state.events.push_back(Event::WindowLeftOutput {
+                                window: idx,
+                                output: name,
+                            });
+                        }
+                    }
+                    for idx in 0..state.windows.len() {
+                        let result = state.update_scale(qhandle, idx);
+                        state.store_error(result);
+                    }
+                    if output.ready {
+                        state
+                            .events
+                            .push_back(Event::OutputRemoved { output: name });
+                    }
+                }
             }
+            _ => (),
         }
     }
 }
@@ -53,7 +296,7 @@ impl Dispatch<wl_compositor::WlCompositor, ()> for State {
     fn event(
         _state: &mut Self,
         _proxy: &wl_compositor::WlCompositor,
-        event: <wl_compositor::WlCompositor as wayland_client::Proxy>::Event,
+        _event: <wl_compositor::WlCompositor as wayland_client::Proxy>::Event,
         _data: &(),
         _conn: &Connection,
         _qhandle: &QueueHandle<Self>,
@@ -65,7 +308,7 @@ impl Dispatch<wl_shm::WlShm, ()> for State {
     fn event(
         _state: &mut Self,
         _proxy: &wl_shm::WlShm,
-        event: <wl_shm::WlShm as wayland_client::Proxy>::Event,
+        _event: <wl_shm::WlShm as wayland_client::Proxy>::Event,
         _data: &(),
         _conn: &Connection,
         _qhandle: &QueueHandle<Self>,
@@ -88,11 +331,12 @@ impl Dispatch<xdg_wm_base::XdgWmBase, ()> for State {
     }
 }
 
+// cursor surfaces
 impl Dispatch<wl_surface::WlSurface, ()> for State {
     fn event(
         _state: &mut Self,
         _proxy: &wl_surface::WlSurface,
-        event: <wl_surface::WlSurface as wayland_client::Proxy>::Event,
+        _event: <wl_surface::WlSurface as wayland_client::Proxy>::Event,
         _data: &(),
         _conn: &Connection,
         _qhandle: &QueueHandle<Self>,
@@ -100,6 +344,55 @@ impl Dispatch<wl_surface::WlSurface, ()> for State {
     }
 }
 
+impl Dispatch<wl_surface::WlSurface, usize> for State {
+    fn event(
+        state: &mut Self,
+        _proxy: &wl_surface::WlSurface,
+        event: <wl_surface::WlSurface as wayland_client::Proxy>::Event,
+        idx: &usize,
+        _conn: &Connection,
+        qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(window) = state.windows.get_mut(*idx) else {
+            return;
+        };
+
+        match event {
+            wl_surface::Event::PreferredBufferScale { factor } => {
+                window.preferred_scale = Some(factor);
+            }
+            wl_surface::Event::Enter { output } => {
+                let Some(&id) = output.data::<u32>() else {
+                    return;
+                };
+                if !window.outputs.contains(&id) {
+                    window.outputs.push(id);
+                    state.events.push_back(Event::WindowEnteredOutput {
+                        window: *idx,
+                        output: id,
+                    });
+                }
+            }
+            wl_surface::Event::Leave { output } => {
+                let Some(&id) = output.data::<u32>() else {
+                    return;
+                };
+                if let Some(pos) = window.outputs.iter().position(|other| *other == id) {
+                    window.outputs.remove(pos);
+                    state.events.push_back(Event::WindowLeftOutput {
+                        window: *idx,
+                        output: id,
+                    });
+                }
+            }
+            _ => return,
+        }
+
+        let result = state.update_scale(qhandle, *idx);
+        state.store_error(result);
+    }
+}
+
 impl Dispatch<xdg_surface::XdgSurface, usize> for State {
     fn event(
         state: &mut Self,
@@ -110,17 +403,13 @@ impl Dispatch<xdg_surface::XdgSurface, usize> for State {
         qhandle: &QueueHandle<Self>,
     ) {
         if let xdg_surface::Event::Configure { serial } = event {
-            match state.windows.get(*idx) {
-                Some(window) => {
-                    println!(
-                        ". ({idx}) ack_configure() needs_resizing({}) to ({}x{})",
-                        window.needs_resizing, window.width, window.height
-                    );
-                    if window.needs_resizing {
-                        State::resize_buffer(state, &qhandle, *idx).unwrap();
-                    }
-                }
-                _ => (),
+            if state
+                .windows
+                .get(*idx)
+                .is_some_and(|window| window.needs_resizing)
+            {
+                let result = state.resize_buffer(qhandle, *idx);
+                state.store_error(result);
             }
             proxy.ack_configure(serial);
             // state.windows.get(*idx).unwrap().surface.commit();
@@ -140,13 +429,8 @@ impl wayland_client::Dispatch<xdg_toplevel::XdgToplevel, usize> for State {
         if let Some(window) = state.windows.get_mut(*idx) {
             match event {
                 xdg_toplevel::Event::Configure { width, height, .. } => {
-                    window.needs_resizing = if (window.width != width || window.height != height)
-                        || window.needs_resizing
-                    {
-                        true
-                    } else {
-                        false
-                    };
+                    window.needs_resizing =
+                        (window.width != width || window.height != height) || window.needs_resizing;
 
                     if width > 0 {
                         window.width = width;
@@ -154,11 +438,6 @@ impl wayland_client::Dispatch<xdg_toplevel::XdgToplevel, usize> for State {
                     if height > 0 {
                         window.height = height;
                     }
-
-                    println!(
-                        ". ({idx}) ({}x{}) => ({}x{}) ({})",
-                        window.width, window.height, width, height, window.needs_resizing
-                    );
                 }
 
                 xdg_toplevel::Event::Close => {
@@ -191,20 +470,34 @@ impl Dispatch<wl_buffer::WlBuffer, usize> for State {
         _conn: &Connection,
         _qhandle: &QueueHandle<Self>,
     ) {
-        if let wl_buffer::Event::Release = event {
-            println!(". ({idx}) buffer event");
-            if let Some(window) = state.windows.get_mut(*idx) {
-                if let Some(buffer) = window
-                    .buffers
-                    .iter_mut()
-                    .find(|b| b.data.id() == proxy.id())
-                {
-                    buffer.used = false;
-                }
-                window
-                    .buffers
-                    .retain(|buffer| !buffer.destroy || buffer.used);
+        if let wl_buffer::Event::Release = event
+            && let Some(window) = state.windows.get_mut(*idx)
+        {
+            if let Some(buffer) = window
+                .buffers
+                .iter_mut()
+                .find(|b| b.data.id() == proxy.id())
+            {
+                buffer.used = false;
             }
+            window.cleanup_buffers();
+        }
+    }
+}
+
+impl Dispatch<wl_buffer::WlBuffer, CustomCursorBuffer> for State {
+    fn event(
+        state: &mut Self,
+        proxy: &wl_buffer::WlBuffer,
+        event: <wl_buffer::WlBuffer as wayland_client::Proxy>::Event,
+        data: &CustomCursorBuffer,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        if let wl_buffer::Event::Release = event
+            && let Some(cursor) = state.custom_cursors.get_mut(data.cursor)
+        {
+            cursor.release_buffer(proxy);
         }
     }
 }
@@ -212,32 +505,1790 @@ impl Dispatch<wl_buffer::WlBuffer, usize> for State {
 impl Dispatch<wl_callback::WlCallback, usize> for State {
     fn event(
         state: &mut Self,
-        proxy: &wl_callback::WlCallback,
+        _proxy: &wl_callback::WlCallback,
         event: <wl_callback::WlCallback as Proxy>::Event,
         idx: &usize,
-        conn: &Connection,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        if let wl_callback::Event::Done { callback_data } = event
+            && let Some(window) = state.windows.get_mut(*idx)
+        {
+            window.frame = None;
+            if state.control_flow == ControlFlow::Continuous {
+                window.redraw_requested = true;
+            }
+
+            let interval = window.frame_stats.record(callback_data);
+            state.events.push_back(Event::FrameReady {
+                window: *idx,
+                time: callback_data,
+                interval,
+            });
+        }
+    }
+}
+
+impl Dispatch<wp_presentation::WpPresentation, ()> for State {
+    fn event(
+        state: &mut Self,
+        _proxy: &wp_presentation::WpPresentation,
+        event: wp_presentation::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        if let wp_presentation::Event::ClockId { clk_id } = event {
+            state.presentation_clock = Some(clk_id);
+        }
+    }
+}
+
+impl Dispatch<wp_presentation_feedback::WpPresentationFeedback, FeedbackData> for State {
+    fn event(
+        state: &mut Self,
+        _proxy: &wp_presentation_feedback::WpPresentationFeedback,
+        event: wp_presentation_feedback::Event,
+        data: &FeedbackData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            wp_presentation_feedback::Event::Presented {
+                tv_sec_hi,
+                tv_sec_lo,
+                tv_nsec,
+                refresh,
+                seq_hi,
+                seq_lo,
+                flags,
+            } => {
+                let info = PresentationInfo::from_feedback(
+                    (tv_sec_hi, tv_sec_lo, tv_nsec),
+                    refresh,
+                    (seq_hi, seq_lo),
+                    flags
+                        .into_result()
+                        .unwrap_or(wp_presentation_feedback::Kind::empty()),
+                );
+
+                if let Some(window) = state.windows.get_mut(data.window) {
+                    window.last_presented = Some(info);
+                }
+                state.events.push_back(Event::Presented {
+                    window: data.window,
+                    frame: data.frame,
+                    info,
+                });
+            }
+            wp_presentation_feedback::Event::Discarded => {
+                state.events.push_back(Event::Discarded {
+                    window: data.window,
+                    frame: data.frame,
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wl_output::WlOutput, u32> for State {
+    fn event(
+        state: &mut Self,
+        _proxy: &wl_output::WlOutput,
+        event: wl_output::Event,
+        id: &u32,
+        _conn: &Connection,
         qhandle: &QueueHandle<Self>,
     ) {
-        println!("* Window ({idx}) can draw now (frame request) <- compositor");
+        let Some(output) = state.outputs.iter_mut().find(|output| output.id == *id) else {
+            return;
+        };
 
-        if let Some(window) = state.windows.get_mut(*idx) {
-            if let Some(buffer) = window
-                .buffers
-                .iter_mut()
-                .filter(|buffer| (!buffer.destroy && !buffer.used))
-                .next()
-            {
-                buffer.used = true; // FIXME: for some reason if i do this some windows lost
-                // their buffer, if i don't nothing wrong seems to happen
-                window.surface.attach(Some(&buffer.data), 0, 0);
-                window.surface.damage(0, 0, window.width, window.height);
-                window.surface.commit();
-            } else {
-                println!("!! Warning: No available buffer for window {}", idx);
+        match event {
+            wl_output::Event::Geometry {
+                x,
+                y,
+                physical_width,
+                physical_height,
+                subpixel,
+                make,
+                model,
+                transform,
+            } => {
+                if output.xdg_output.is_none() {
+                    output.logical_x = x;
+                    output.logical_y = y;
+                }
+                output.physical_width = physical_width;
+                output.physical_height = physical_height;
+                output.make = make;
+                output.model = model;
+                if let Ok(subpixel) = subpixel.into_result() {
+                    output.subpixel = subpixel;
+                }
+                if let Ok(transform) = transform.into_result() {
+                    output.transform = transform;
+                }
+            }
+            wl_output::Event::Mode {
+                flags,
+                width,
+                height,
+                refresh,
+            } => {
+                let flags = flags.into_result().unwrap_or(wl_output::Mode::empty());
+                output.update_mode(Mode {
+                    width,
+                    height,
+                    refresh,
+                    current: flags.contains(wl_output::Mode::Current),
+                    preferred: flags.contains(wl_output::Mode::Preferred),
+                });
+            }
+            wl_output::Event::Scale { factor } => output.scale = factor,
+            wl_output::Event::Name { name } => output.name = Some(name),
+            wl_output::Event::Description { description } => output.description = Some(description),
+            wl_output::Event::Done => {
+                output.done = true;
+                if output.ready {
+                    state.events.push_back(Event::OutputChanged { output: *id });
+                } else if output.is_complete() {
+                    output.ready = true;
+                    state.events.push_back(Event::OutputAdded { output: *id });
+                }
+
+                let windows: Vec<usize> = state
+                    .windows
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, window)| window.outputs.contains(id))
+                    .map(|(idx, _)| idx)
+                    .collect();
+                for idx in windows {
+                    let result = state.update_scale(qhandle, idx);
+                    state.store_error(result);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zxdg_output_manager_v1::ZxdgOutputManagerV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &zxdg_output_manager_v1::ZxdgOutputManagerV1,
+        _event: zxdg_output_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<zxdg_output_v1::ZxdgOutputV1, u32> for State {
+    fn event(
+        state: &mut Self,
+        _proxy: &zxdg_output_v1::ZxdgOutputV1,
+        event: zxdg_output_v1::Event,
+        id: &u32,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(output) = state.outputs.iter_mut().find(|output| output.id == *id) else {
+            return;
+        };
+
+        // zxdg_output_v1.done is deprecated from version 3 on, changes are followed by
+        // wl_output.done instead which is where events are emitted
+        match event {
+            zxdg_output_v1::Event::LogicalPosition { x, y } => {
+                output.logical_x = x;
+                output.logical_y = y;
+                output.logical_position_known = true;
+            }
+            zxdg_output_v1::Event::LogicalSize { width, height } => {
+                output.logical_width = Some(width);
+                output.logical_height = Some(height);
+            }
+            zxdg_output_v1::Event::Name { name } => output.name = Some(name),
+            zxdg_output_v1::Event::Description { description } => {
+                output.description = Some(description)
+            }
+            // older versions may only send their details after wl_output.done
+            zxdg_output_v1::Event::Done if !output.ready && output.is_complete() => {
+                output.ready = true;
+                state.events.push_back(Event::OutputAdded { output: *id });
             }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1,
+        _event: wp_fractional_scale_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<wp_fractional_scale_v1::WpFractionalScaleV1, usize> for State {
+    fn event(
+        state: &mut Self,
+        _proxy: &wp_fractional_scale_v1::WpFractionalScaleV1,
+        event: wp_fractional_scale_v1::Event,
+        idx: &usize,
+        _conn: &Connection,
+        qhandle: &QueueHandle<Self>,
+    ) {
+        if let wp_fractional_scale_v1::Event::PreferredScale { scale } = event
+            && let Some(window) = state.windows.get_mut(*idx)
+        {
+            window.preferred_fractional_scale = Some(scale);
+            let result = state.update_scale(qhandle, *idx);
+            state.store_error(result);
+        }
+    }
+}
+
+impl Dispatch<wp_viewporter::WpViewporter, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &wp_viewporter::WpViewporter,
+        _event: wp_viewporter::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<wp_viewport::WpViewport, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &wp_viewport::WpViewport,
+        _event: wp_viewport::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<wl_seat::WlSeat, u32> for State {
+    fn event(
+        state: &mut Self,
+        _proxy: &wl_seat::WlSeat,
+        event: wl_seat::Event,
+        id: &u32,
+        _conn: &Connection,
+        qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(idx) = state.seats.iter().position(|seat| seat.id == *id) else {
+            return;
+        };
 
-            let frame = window.surface.frame(&qhandle, *idx);
-            window.frame = frame;
+        match event {
+            wl_seat::Event::Name { name } => state.seats[idx].name = Some(name),
+            wl_seat::Event::Capabilities { capabilities } => {
+                let capabilities = capabilities
+                    .into_result()
+                    .unwrap_or(wl_seat::Capability::empty());
+                state.update_capabilities(qhandle, idx, capabilities);
+            }
+            _ => {}
         }
     }
 }
+
+impl Dispatch<wl_keyboard::WlKeyboard, u32> for State {
+    fn event(
+        state: &mut Self,
+        _proxy: &wl_keyboard::WlKeyboard,
+        event: wl_keyboard::Event,
+        id: &u32,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(seat) = state.seats.iter_mut().find(|seat| seat.id == *id) else {
+            return;
+        };
+        let Some(keyboard) = seat.keyboard.as_mut() else {
+            return;
+        };
+
+        match event {
+            wl_keyboard::Event::Keymap { format, fd, size } => {
+                keyboard.xkb = match format.into_result() {
+                    Ok(wl_keyboard::KeymapFormat::XkbV1) => Xkb::from_fd(fd, size),
+                    _ => None,
+                };
+                if keyboard.xkb.is_none() {
+                    state.events.push_back(Event::KeymapFailed { seat: *id });
+                }
+            }
+            wl_keyboard::Event::Enter {
+                serial, surface, ..
+            } => {
+                let Some(&window) = surface.data::<usize>() else {
+                    return;
+                };
+                keyboard.focus = Some(window);
+                keyboard.serial = serial;
+                state
+                    .events
+                    .push_back(Event::KeyboardEnter { window, seat: *id });
+            }
+            wl_keyboard::Event::Leave { serial, .. } => {
+                keyboard.serial = serial;
+                keyboard.repeat = None;
+                keyboard.reset_compose();
+                if let Some(window) = keyboard.focus.take() {
+                    state
+                        .events
+                        .push_back(Event::KeyboardLeave { window, seat: *id });
+                }
+            }
+            wl_keyboard::Event::Key {
+                serial,
+                time,
+                key,
+                state: key_state,
+            } => {
+                keyboard.serial = serial;
+                if matches!(key_state.into_result(), Ok(wl_keyboard::KeyState::Pressed)) {
+                    seat.press_serial = Some(serial);
+                }
+                let Some(window) = keyboard.focus else {
+                    return;
+                };
+
+                let keycode = key;
+                match key_state.into_result() {
+                    Ok(wl_keyboard::KeyState::Pressed) => {
+                        let key = keyboard.press_event(keycode, serial, time);
+                        keyboard.start_repeat(window, keycode, serial, time);
+                        let text = key.text.clone();
+                        state.events.push_back(Event::KeyPressed {
+                            window,
+                            seat: *id,
+                            key,
+                        });
+                        if let Some(text) = text {
+                            state.events.push_back(Event::Text {
+                                window,
+                                seat: *id,
+                                text,
+                            });
+                        }
+                    }
+                    Ok(wl_keyboard::KeyState::Released) => {
+                        let key = keyboard.key_event(keycode, serial, time);
+                        keyboard.stop_repeat(keycode);
+                        state.events.push_back(Event::KeyReleased {
+                            window,
+                            seat: *id,
+                            key,
+                        });
+                    }
+                    _ => {}
+                }
+            }
+            wl_keyboard::Event::RepeatInfo { rate, delay } => {
+                keyboard.repeat_info = RepeatInfo { rate, delay };
+                if rate <= 0 {
+                    keyboard.repeat = None;
+                }
+            }
+            wl_keyboard::Event::Modifiers {
+                mods_depressed,
+                mods_latched,
+                mods_locked,
+                group,
+                ..
+            } => {
+                let Some(xkb) = keyboard.xkb.as_mut() else {
+                    return;
+                };
+                xkb.update_modifiers(mods_depressed, mods_latched, mods_locked, group);
+                keyboard.modifiers = xkb.modifiers();
+
+                if let Some(window) = keyboard.focus {
+                    state.events.push_back(Event::ModifiersChanged {
+                        window,
+                        seat: *id,
+                        modifiers: keyboard.modifiers,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wl_pointer::WlPointer, u32> for State {
+    fn event(
+        state: &mut Self,
+        proxy: &wl_pointer::WlPointer,
+        event: wl_pointer::Event,
+        id: &u32,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(seat) = state.seats.iter_mut().find(|seat| seat.id == *id) else {
+            return;
+        };
+        let Some(pointer) = seat.pointer.as_mut() else {
+            return;
+        };
+        let scale_of = |window: usize| {
+            state
+                .windows
+                .get(window)
+                .map(|window| window.scale_factor())
+                .unwrap_or(1.0)
+        };
+        let is_frame = matches!(event, wl_pointer::Event::Frame);
+
+        match event {
+            wl_pointer::Event::Enter {
+                serial,
+                surface,
+                surface_x,
+                surface_y,
+            } => {
+                let Some(&window) = surface.data::<usize>() else {
+                    return;
+                };
+                let scale = scale_of(window);
+                pointer.focus = Some(window);
+                pointer.enter_serial = serial;
+                // every enter needs its own set_cursor with the new serial
+                if let Some(cursor) = pointer.cursor.as_mut() {
+                    cursor.applied = None;
+                }
+                pointer.cursor_shape_applied = None;
+                pointer.position = (surface_x, surface_y);
+                pointer.pending.push((
+                    window,
+                    PointerEvent::Enter {
+                        position: (surface_x, surface_y),
+                        buffer_position: (surface_x * scale, surface_y * scale),
+                        serial,
+                    },
+                ));
+            }
+            wl_pointer::Event::Leave { serial, .. } => {
+                if let Some(window) = pointer.focus.take() {
+                    pointer
+                        .pending
+                        .push((window, PointerEvent::Leave { serial }));
+                }
+            }
+            wl_pointer::Event::Motion {
+                time,
+                surface_x,
+                surface_y,
+            } => {
+                let Some(window) = pointer.focus else {
+                    return;
+                };
+                let scale = scale_of(window);
+                pointer.position = (surface_x, surface_y);
+                pointer.pending.push((
+                    window,
+                    PointerEvent::Motion {
+                        position: (surface_x, surface_y),
+                        buffer_position: (surface_x * scale, surface_y * scale),
+                        time,
+                    },
+                ));
+            }
+            wl_pointer::Event::Button {
+                serial,
+                time,
+                button,
+                state: button_state,
+            } => {
+                let Some(window) = pointer.focus else {
+                    return;
+                };
+                pointer.button_serial = serial;
+                if matches!(
+                    button_state.into_result(),
+                    Ok(wl_pointer::ButtonState::Pressed)
+                ) {
+                    seat.press_serial = Some(serial);
+                }
+                pointer.pending.push((
+                    window,
+                    PointerEvent::Button {
+                        button,
+                        pressed: matches!(
+                            button_state.into_result(),
+                            Ok(wl_pointer::ButtonState::Pressed)
+                        ),
+                        serial,
+                        time,
+                    },
+                ));
+            }
+            wl_pointer::Event::Axis { time, axis, value } => {
+                if let Some(values) = pointer.axis_frame(Some(time)).axis(axis) {
+                    values.absolute += value;
+                }
+            }
+            wl_pointer::Event::AxisSource { axis_source } => {
+                pointer.axis_frame(None).source = axis_source.into_result().ok();
+            }
+            wl_pointer::Event::AxisStop { time, axis } => {
+                if let Some(values) = pointer.axis_frame(Some(time)).axis(axis) {
+                    values.stop = true;
+                }
+            }
+            wl_pointer::Event::AxisDiscrete { axis, discrete } => {
+                // superseded by axis_value120 from version 8 on
+                if let Some(values) = pointer.axis_frame(None).axis(axis) {
+                    values.value120 += discrete * 120;
+                }
+            }
+            wl_pointer::Event::AxisValue120 { axis, value120 } => {
+                if let Some(values) = pointer.axis_frame(None).axis(axis) {
+                    values.value120 += value120;
+                }
+            }
+            wl_pointer::Event::Frame => {}
+            _ => return,
+        }
+
+        // before version 5 there are no frame events, every event stands on its own
+        if is_frame || proxy.version() < 5 {
+            for (window, events) in pointer.take_frame() {
+                state.events.push_back(Event::Pointer {
+                    window,
+                    seat: *id,
+                    events,
+                });
+            }
+        }
+    }
+}
+
+impl Dispatch<zwp_pointer_gestures_v1::ZwpPointerGesturesV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &zwp_pointer_gestures_v1::ZwpPointerGesturesV1,
+        _event: zwp_pointer_gestures_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<zwp_pointer_gesture_swipe_v1::ZwpPointerGestureSwipeV1, u32> for State {
+    fn event(
+        state: &mut Self,
+        _proxy: &zwp_pointer_gesture_swipe_v1::ZwpPointerGestureSwipeV1,
+        event: zwp_pointer_gesture_swipe_v1::Event,
+        id: &u32,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_pointer_gesture_swipe_v1::Event::Begin {
+                serial,
+                time,
+                surface,
+                fingers,
+            } => state.push_gesture(
+                *id,
+                Some(&surface),
+                GestureEvent::SwipeBegin {
+                    fingers,
+                    serial,
+                    time,
+                },
+            ),
+            zwp_pointer_gesture_swipe_v1::Event::Update { time, dx, dy } => state.push_gesture(
+                *id,
+                None,
+                GestureEvent::SwipeUpdate {
+                    delta: (dx, dy),
+                    time,
+                },
+            ),
+            zwp_pointer_gesture_swipe_v1::Event::End {
+                serial,
+                time,
+                cancelled,
+            } => state.push_gesture(
+                *id,
+                None,
+                GestureEvent::SwipeEnd {
+                    cancelled: cancelled != 0,
+                    serial,
+                    time,
+                },
+            ),
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zwp_pointer_gesture_pinch_v1::ZwpPointerGesturePinchV1, u32> for State {
+    fn event(
+        state: &mut Self,
+        _proxy: &zwp_pointer_gesture_pinch_v1::ZwpPointerGesturePinchV1,
+        event: zwp_pointer_gesture_pinch_v1::Event,
+        id: &u32,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_pointer_gesture_pinch_v1::Event::Begin {
+                serial,
+                time,
+                surface,
+                fingers,
+            } => state.push_gesture(
+                *id,
+                Some(&surface),
+                GestureEvent::PinchBegin {
+                    fingers,
+                    serial,
+                    time,
+                },
+            ),
+            zwp_pointer_gesture_pinch_v1::Event::Update {
+                time,
+                dx,
+                dy,
+                scale,
+                rotation,
+            } => state.push_gesture(
+                *id,
+                None,
+                GestureEvent::PinchUpdate {
+                    delta: (dx, dy),
+                    scale,
+                    rotation,
+                    time,
+                },
+            ),
+            zwp_pointer_gesture_pinch_v1::Event::End {
+                serial,
+                time,
+                cancelled,
+            } => state.push_gesture(
+                *id,
+                None,
+                GestureEvent::PinchEnd {
+                    cancelled: cancelled != 0,
+                    serial,
+                    time,
+                },
+            ),
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zwp_pointer_gesture_hold_v1::ZwpPointerGestureHoldV1, u32> for State {
+    fn event(
+        state: &mut Self,
+        _proxy: &zwp_pointer_gesture_hold_v1::ZwpPointerGestureHoldV1,
+        event: zwp_pointer_gesture_hold_v1::Event,
+        id: &u32,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_pointer_gesture_hold_v1::Event::Begin {
+                serial,
+                time,
+                surface,
+                fingers,
+            } => state.push_gesture(
+                *id,
+                Some(&surface),
+                GestureEvent::HoldBegin {
+                    fingers,
+                    serial,
+                    time,
+                },
+            ),
+            zwp_pointer_gesture_hold_v1::Event::End {
+                serial,
+                time,
+                cancelled,
+            } => state.push_gesture(
+                *id,
+                None,
+                GestureEvent::HoldEnd {
+                    cancelled: cancelled != 0,
+                    serial,
+                    time,
+                },
+            ),
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wl_region::WlRegion, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &wl_region::WlRegion,
+        _event: wl_region::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<zwp_pointer_constraints_v1::ZwpPointerConstraintsV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &zwp_pointer_constraints_v1::ZwpPointerConstraintsV1,
+        _event: zwp_pointer_constraints_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<zwp_locked_pointer_v1::ZwpLockedPointerV1, ConstraintData> for State {
+    fn event(
+        state: &mut Self,
+        _proxy: &zwp_locked_pointer_v1::ZwpLockedPointerV1,
+        event: zwp_locked_pointer_v1::Event,
+        data: &ConstraintData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let (window, seat) = (data.window, data.seat);
+        match event {
+            zwp_locked_pointer_v1::Event::Locked => {
+                state.set_constraint_active(data, true);
+                state
+                    .events
+                    .push_back(Event::PointerLocked { window, seat });
+            }
+            zwp_locked_pointer_v1::Event::Unlocked => {
+                state.set_constraint_active(data, false);
+                state
+                    .events
+                    .push_back(Event::PointerUnlocked { window, seat });
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zwp_confined_pointer_v1::ZwpConfinedPointerV1, ConstraintData> for State {
+    fn event(
+        state: &mut Self,
+        _proxy: &zwp_confined_pointer_v1::ZwpConfinedPointerV1,
+        event: zwp_confined_pointer_v1::Event,
+        data: &ConstraintData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let (window, seat) = (data.window, data.seat);
+        match event {
+            zwp_confined_pointer_v1::Event::Confined => {
+                state.set_constraint_active(data, true);
+                state
+                    .events
+                    .push_back(Event::PointerConfined { window, seat });
+            }
+            zwp_confined_pointer_v1::Event::Unconfined => {
+                state.set_constraint_active(data, false);
+                state
+                    .events
+                    .push_back(Event::PointerUnconfined { window, seat });
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zwp_relative_pointer_manager_v1::ZwpRelativePointerManagerV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &zwp_relative_pointer_manager_v1::ZwpRelativePointerManagerV1,
+        _event: zwp_relative_pointer_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<zwp_relative_pointer_v1::ZwpRelativePointerV1, u32> for State {
+    fn event(
+        state: &mut Self,
+        _proxy: &zwp_relative_pointer_v1::ZwpRelativePointerV1,
+        event: zwp_relative_pointer_v1::Event,
+        id: &u32,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(pointer) = state
+            .seats
+            .iter_mut()
+            .find(|seat| seat.id == *id)
+            .and_then(|seat| seat.pointer.as_mut())
+        else {
+            return;
+        };
+        if let zwp_relative_pointer_v1::Event::RelativeMotion {
+            utime_hi,
+            utime_lo,
+            dx,
+            dy,
+            dx_unaccel,
+            dy_unaccel,
+        } = event
+            && let Some(window) = pointer.focus
+        {
+            pointer.pending.push((
+                window,
+                PointerEvent::RelativeMotion {
+                    delta: (dx, dy),
+                    delta_unaccelerated: (dx_unaccel, dy_unaccel),
+                    utime: ((utime_hi as u64) << 32) | utime_lo as u64,
+                },
+            ));
+            // relative motion belongs to the next wl_pointer.frame, older pointers have none
+            if pointer.wl_pointer.version() < 5 {
+                for (window, events) in pointer.take_frame() {
+                    state.events.push_back(Event::Pointer {
+                        window,
+                        seat: *id,
+                        events,
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl Dispatch<zwp_tablet_manager_v2::ZwpTabletManagerV2, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &zwp_tablet_manager_v2::ZwpTabletManagerV2,
+        _event: zwp_tablet_manager_v2::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<zwp_tablet_seat_v2::ZwpTabletSeatV2, u32> for State {
+    fn event(
+        state: &mut Self,
+        _proxy: &zwp_tablet_seat_v2::ZwpTabletSeatV2,
+        event: zwp_tablet_seat_v2::Event,
+        id: &u32,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(tablet_seat) = state
+            .seats
+            .iter_mut()
+            .find(|seat| seat.id == *id)
+            .and_then(|seat| seat.tablet.as_mut())
+        else {
+            return;
+        };
+
+        // the *_added events come first, the new object then describes itself and ends with
+        // done, which is when our own Added event is sent
+        match event {
+            zwp_tablet_seat_v2::Event::TabletAdded { id } => {
+                tablet_seat.tablets.push(Tablet::new(id));
+            }
+            zwp_tablet_seat_v2::Event::ToolAdded { id } => {
+                tablet_seat.tools.push(TabletTool::new(id));
+            }
+            zwp_tablet_seat_v2::Event::PadAdded { id } => {
+                tablet_seat.pads.push(TabletPad::new(id));
+            }
+            _ => {}
+        }
+    }
+
+    wayland_client::event_created_child!(State, zwp_tablet_seat_v2::ZwpTabletSeatV2, [
+        zwp_tablet_seat_v2::EVT_TABLET_ADDED_OPCODE => (zwp_tablet_v2::ZwpTabletV2, ()),
+        zwp_tablet_seat_v2::EVT_TOOL_ADDED_OPCODE => (zwp_tablet_tool_v2::ZwpTabletToolV2, ()),
+        zwp_tablet_seat_v2::EVT_PAD_ADDED_OPCODE => (zwp_tablet_pad_v2::ZwpTabletPadV2, ()),
+    ]);
+}
+
+impl Dispatch<zwp_tablet_v2::ZwpTabletV2, ()> for State {
+    fn event(
+        state: &mut Self,
+        proxy: &zwp_tablet_v2::ZwpTabletV2,
+        event: zwp_tablet_v2::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some((seat, tablet_seat, pos)) = state.seats.iter_mut().find_map(|seat| {
+            let tablet_seat = seat.tablet.as_mut()?;
+            let pos = tablet_seat
+                .tablets
+                .iter()
+                .position(|tablet| tablet.zwp_tablet == *proxy)?;
+            Some((seat.id, tablet_seat, pos))
+        }) else {
+            return;
+        };
+        let tablet = &mut tablet_seat.tablets[pos];
+
+        match event {
+            zwp_tablet_v2::Event::Name { name } => tablet.name = Some(name),
+            zwp_tablet_v2::Event::Id { vid, pid } => {
+                tablet.vid = Some(vid);
+                tablet.pid = Some(pid);
+            }
+            zwp_tablet_v2::Event::Path { path } => tablet.paths.push(path),
+            zwp_tablet_v2::Event::Done if !tablet.ready => {
+                tablet.ready = true;
+                state.events.push_back(Event::TabletAdded {
+                    seat,
+                    tablet: tablet.id,
+                });
+            }
+            zwp_tablet_v2::Event::Removed => {
+                let tablet = tablet_seat.tablets.remove(pos);
+                tablet.zwp_tablet.destroy();
+                if tablet.ready {
+                    state.events.push_back(Event::TabletRemoved {
+                        seat,
+                        tablet: tablet.id,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zwp_tablet_tool_v2::ZwpTabletToolV2, ()> for State {
+    fn event(
+        state: &mut Self,
+        proxy: &zwp_tablet_tool_v2::ZwpTabletToolV2,
+        event: zwp_tablet_tool_v2::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some((seat, tablet_seat, pos)) = state.seats.iter_mut().find_map(|seat| {
+            let tablet_seat = seat.tablet.as_mut()?;
+            let pos = tablet_seat
+                .tools
+                .iter()
+                .position(|tool| tool.zwp_tablet_tool == *proxy)?;
+            Some((seat.id, tablet_seat, pos))
+        }) else {
+            return;
+        };
+        let tool = &mut tablet_seat.tools[pos];
+        let scale_of = |window: usize| {
+            state
+                .windows
+                .get(window)
+                .map(|window| window.scale_factor())
+                .unwrap_or(1.0)
+        };
+
+        match event {
+            zwp_tablet_tool_v2::Event::Type { tool_type } => {
+                tool.tool_type = tool_type.into_result().ok();
+            }
+            zwp_tablet_tool_v2::Event::HardwareSerial {
+                hardware_serial_hi,
+                hardware_serial_lo,
+            } => {
+                tool.hardware_serial =
+                    Some(((hardware_serial_hi as u64) << 32) | hardware_serial_lo as u64);
+            }
+            zwp_tablet_tool_v2::Event::HardwareIdWacom {
+                hardware_id_hi,
+                hardware_id_lo,
+            } => {
+                tool.hardware_id = Some(((hardware_id_hi as u64) << 32) | hardware_id_lo as u64);
+            }
+            zwp_tablet_tool_v2::Event::Capability { capability } => {
+                if let Ok(capability) = capability.into_result() {
+                    tool.capabilities.push(capability);
+                }
+            }
+            zwp_tablet_tool_v2::Event::Done if !tool.ready => {
+                tool.ready = true;
+                state.events.push_back(Event::TabletToolAdded {
+                    seat,
+                    tool: tool.id,
+                });
+            }
+            zwp_tablet_tool_v2::Event::Removed => {
+                let tool = tablet_seat.tools.remove(pos);
+                tool.zwp_tablet_tool.destroy();
+                if tool.ready {
+                    state.events.push_back(Event::TabletToolRemoved {
+                        seat,
+                        tool: tool.id,
+                    });
+                }
+            }
+            zwp_tablet_tool_v2::Event::ProximityIn {
+                serial,
+                tablet,
+                surface,
+            } => {
+                tool.focus = surface.data::<usize>().copied();
+                tool.pending.push(TabletToolEvent::ProximityIn {
+                    tablet: tablet.id().protocol_id(),
+                    serial,
+                });
+            }
+            zwp_tablet_tool_v2::Event::ProximityOut => {
+                tool.pending.push(TabletToolEvent::ProximityOut);
+            }
+            zwp_tablet_tool_v2::Event::Down { serial } => {
+                tool.pending.push(TabletToolEvent::Down { serial });
+            }
+            zwp_tablet_tool_v2::Event::Up => tool.pending.push(TabletToolEvent::Up),
+            zwp_tablet_tool_v2::Event::Motion { x, y } => {
+                let scale = tool.focus.map(scale_of).unwrap_or(1.0);
+                tool.pending.push(TabletToolEvent::Motion {
+                    position: (x, y),
+                    buffer_position: (x * scale, y * scale),
+                });
+            }
+            zwp_tablet_tool_v2::Event::Pressure { pressure } => {
+                tool.pending.push(TabletToolEvent::pressure(pressure));
+            }
+            zwp_tablet_tool_v2::Event::Distance { distance } => {
+                tool.pending.push(TabletToolEvent::distance(distance));
+            }
+            zwp_tablet_tool_v2::Event::Tilt { tilt_x, tilt_y } => {
+                tool.pending.push(TabletToolEvent::Tilt {
+                    x: tilt_x,
+                    y: tilt_y,
+                });
+            }
+            zwp_tablet_tool_v2::Event::Rotation { degrees } => {
+                tool.pending.push(TabletToolEvent::Rotation(degrees));
+            }
+            zwp_tablet_tool_v2::Event::Slider { position } => {
+                tool.pending.push(TabletToolEvent::slider(position));
+            }
+            zwp_tablet_tool_v2::Event::Wheel { degrees, clicks } => {
+                tool.pending
+                    .push(TabletToolEvent::Wheel { degrees, clicks });
+            }
+            zwp_tablet_tool_v2::Event::Button {
+                serial,
+                button,
+                state: button_state,
+            } => {
+                tool.pending.push(TabletToolEvent::Button {
+                    button,
+                    pressed: matches!(
+                        button_state.into_result(),
+                        Ok(zwp_tablet_tool_v2::ButtonState::Pressed)
+                    ),
+                    serial,
+                });
+            }
+            zwp_tablet_tool_v2::Event::Frame { time } => {
+                let events = std::mem::take(&mut tool.pending);
+                let left = events.contains(&TabletToolEvent::ProximityOut);
+                if let Some(window) = tool.focus
+                    && !events.is_empty()
+                {
+                    state.events.push_back(Event::TabletTool {
+                        window,
+                        seat,
+                        tool: tool.id,
+                        events,
+                        time,
+                    });
+                }
+                if left {
+                    tool.focus = None;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zwp_tablet_pad_v2::ZwpTabletPadV2, ()> for State {
+    fn event(
+        state: &mut Self,
+        proxy: &zwp_tablet_pad_v2::ZwpTabletPadV2,
+        event: zwp_tablet_pad_v2::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some((seat, tablet_seat, pos)) = state.seats.iter_mut().find_map(|seat| {
+            let tablet_seat = seat.tablet.as_mut()?;
+            let pos = tablet_seat
+                .pads
+                .iter()
+                .position(|pad| pad.zwp_tablet_pad == *proxy)?;
+            Some((seat.id, tablet_seat, pos))
+        }) else {
+            return;
+        };
+        let pad = &mut tablet_seat.pads[pos];
+
+        let pad_event = match event {
+            zwp_tablet_pad_v2::Event::Group { pad_group } => {
+                pad.groups.push(pad_group);
+                return;
+            }
+            zwp_tablet_pad_v2::Event::Path { path } => {
+                pad.paths.push(path);
+                return;
+            }
+            zwp_tablet_pad_v2::Event::Buttons { buttons } => {
+                pad.buttons = buttons;
+                return;
+            }
+            zwp_tablet_pad_v2::Event::Done => {
+                if !pad.ready {
+                    pad.ready = true;
+                    state
+                        .events
+                        .push_back(Event::TabletPadAdded { seat, pad: pad.id });
+                }
+                return;
+            }
+            zwp_tablet_pad_v2::Event::Removed => {
+                let pad = tablet_seat.pads.remove(pos);
+                pad.destroy();
+                if pad.ready {
+                    state
+                        .events
+                        .push_back(Event::TabletPadRemoved { seat, pad: pad.id });
+                }
+                return;
+            }
+            zwp_tablet_pad_v2::Event::Enter {
+                serial,
+                tablet,
+                surface,
+            } => {
+                pad.focus = surface.data::<usize>().copied();
+                TabletPadEvent::Enter {
+                    tablet: tablet.id().protocol_id(),
+                    serial,
+                }
+            }
+            zwp_tablet_pad_v2::Event::Leave { serial, .. } => {
+                let Some(window) = pad.focus.take() else {
+                    return;
+                };
+                state.events.push_back(Event::TabletPad {
+                    window,
+                    seat,
+                    pad: pad.id,
+                    event: TabletPadEvent::Leave { serial },
+                });
+                return;
+            }
+            zwp_tablet_pad_v2::Event::Button {
+                time,
+                button,
+                state: button_state,
+            } => TabletPadEvent::Button {
+                button,
+                pressed: matches!(
+                    button_state.into_result(),
+                    Ok(zwp_tablet_pad_v2::ButtonState::Pressed)
+                ),
+                time,
+            },
+            _ => return,
+        };
+
+        if let Some(window) = pad.focus {
+            state.events.push_back(Event::TabletPad {
+                window,
+                seat,
+                pad: pad.id,
+                event: pad_event,
+            });
+        }
+    }
+
+    wayland_client::event_created_child!(State, zwp_tablet_pad_v2::ZwpTabletPadV2, [
+        zwp_tablet_pad_v2::EVT_GROUP_OPCODE => (zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2, ()),
+    ]);
+}
+
+impl Dispatch<zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2,
+        event: zwp_tablet_pad_group_v2::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        // rings, strips and dials are not exposed, nothing is listening to them
+        match event {
+            zwp_tablet_pad_group_v2::Event::Ring { ring } => ring.destroy(),
+            zwp_tablet_pad_group_v2::Event::Strip { strip } => strip.destroy(),
+            zwp_tablet_pad_group_v2::Event::Dial { dial } => dial.destroy(),
+            _ => {}
+        }
+    }
+
+    wayland_client::event_created_child!(State, zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2, [
+        zwp_tablet_pad_group_v2::EVT_RING_OPCODE => (zwp_tablet_pad_ring_v2::ZwpTabletPadRingV2, ()),
+        zwp_tablet_pad_group_v2::EVT_STRIP_OPCODE => (zwp_tablet_pad_strip_v2::ZwpTabletPadStripV2, ()),
+        zwp_tablet_pad_group_v2::EVT_DIAL_OPCODE => (zwp_tablet_pad_dial_v2::ZwpTabletPadDialV2, ()),
+    ]);
+}
+
+impl Dispatch<zwp_tablet_pad_ring_v2::ZwpTabletPadRingV2, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &zwp_tablet_pad_ring_v2::ZwpTabletPadRingV2,
+        _event: zwp_tablet_pad_ring_v2::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<zwp_tablet_pad_strip_v2::ZwpTabletPadStripV2, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &zwp_tablet_pad_strip_v2::ZwpTabletPadStripV2,
+        _event: zwp_tablet_pad_strip_v2::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<zwp_tablet_pad_dial_v2::ZwpTabletPadDialV2, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &zwp_tablet_pad_dial_v2::ZwpTabletPadDialV2,
+        _event: zwp_tablet_pad_dial_v2::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<wl_data_device_manager::WlDataDeviceManager, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &wl_data_device_manager::WlDataDeviceManager,
+        _event: wl_data_device_manager::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<wl_data_device::WlDataDevice, u32> for State {
+    fn event(
+        state: &mut Self,
+        _proxy: &wl_data_device::WlDataDevice,
+        event: wl_data_device::Event,
+        id: &u32,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let scale_of = |window: usize| {
+            state
+                .windows
+                .get(window)
+                .map(|window| window.scale_factor())
+                .unwrap_or(1.0)
+        };
+        let Some(device) = state
+            .seats
+            .iter_mut()
+            .find(|seat| seat.id == *id)
+            .and_then(|seat| seat.data_device.as_mut())
+        else {
+            return;
+        };
+
+        match event {
+            wl_data_device::Event::DataOffer { id: wl_data_offer } => {
+                device.offers.push(DataOffer::new(wl_data_offer));
+            }
+            wl_data_device::Event::Selection { id: wl_data_offer } => {
+                let offer = wl_data_offer.and_then(|offer| device.take_offer(&offer));
+                if let Some(old) = std::mem::replace(&mut device.selection, offer) {
+                    old.destroy();
+                }
+                state
+                    .events
+                    .push_back(Event::ClipboardChanged { seat: *id });
+            }
+            wl_data_device::Event::Enter {
+                serial,
+                surface,
+                x,
+                y,
+                id: wl_data_offer,
+            } => {
+                let Some(offer) = wl_data_offer.and_then(|offer| device.take_offer(&offer)) else {
+                    return;
+                };
+                let Some(&window) = surface.data::<usize>() else {
+                    offer.destroy();
+                    return;
+                };
+                let scale = scale_of(window);
+                state.events.push_back(Event::DragEnter {
+                    window,
+                    seat: *id,
+                    position: (x, y),
+                    buffer_position: (x * scale, y * scale),
+                    mime_types: offer.mime_types.clone(),
+                    source_actions: offer.source_actions,
+                });
+                let drag = DragOffer {
+                    offer,
+                    window,
+                    serial,
+                    accepted: None,
+                    dropped: false,
+                };
+                // a drop that was never finished is given up on
+                if let Some(old) = device.drag.replace(drag) {
+                    old.finish();
+                }
+            }
+            wl_data_device::Event::Motion { time, x, y } => {
+                if let Some(drag) = &device.drag {
+                    let scale = scale_of(drag.window);
+                    state.events.push_back(Event::DragMotion {
+                        window: drag.window,
+                        seat: *id,
+                        position: (x, y),
+                        buffer_position: (x * scale, y * scale),
+                        time,
+                    });
+                }
+            }
+            // some compositors send a leave after the drop too, the offer is still needed then
+            wl_data_device::Event::Leave => {
+                if let Some(drag) = device.drag.take_if(|drag| !drag.dropped) {
+                    drag.offer.destroy();
+                    state.events.push_back(Event::DragLeave {
+                        window: drag.window,
+                        seat: *id,
+                    });
+                }
+            }
+            wl_data_device::Event::Drop => {
+                if let Some(drag) = &mut device.drag {
+                    drag.dropped = true;
+                    state.events.push_back(Event::Drop {
+                        window: drag.window,
+                        seat: *id,
+                        action: drag.offer.action,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    wayland_client::event_created_child!(State, wl_data_device::WlDataDevice, [
+        wl_data_device::EVT_DATA_OFFER_OPCODE => (wl_data_offer::WlDataOffer, ()),
+    ]);
+}
+
+impl Dispatch<wl_data_offer::WlDataOffer, ()> for State {
+    fn event(
+        state: &mut Self,
+        proxy: &wl_data_offer::WlDataOffer,
+        event: wl_data_offer::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some((seat, device)) = state.seats.iter_mut().find_map(|seat| {
+            let device = seat.data_device.as_mut()?;
+            device.offer_mut(proxy)?;
+            Some((seat.id, device))
+        }) else {
+            return;
+        };
+        let Some(offer) = device.offer_mut(proxy) else {
+            return;
+        };
+
+        match event {
+            wl_data_offer::Event::Offer { mime_type } => {
+                offer.mime_types.push(mime_type);
+            }
+            wl_data_offer::Event::SourceActions { source_actions } => {
+                offer.source_actions = source_actions.into_result().unwrap_or(DndAction::empty());
+            }
+            wl_data_offer::Event::Action { dnd_action } => {
+                let action = dnd_action.into_result().unwrap_or(DndAction::empty());
+                offer.action = action;
+                if let Some(drag) = &device.drag
+                    && drag.offer.wl_data_offer == *proxy
+                {
+                    state.events.push_back(Event::DragActionChanged {
+                        window: drag.window,
+                        seat,
+                        action,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wl_data_source::WlDataSource, ()> for State {
+    fn event(
+        state: &mut Self,
+        proxy: &wl_data_source::WlDataSource,
+        event: wl_data_source::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let source = state
+            .clipboard_source
+            .as_ref()
+            .filter(|source| source.wl_data_source == *proxy);
+        let drag = state
+            .drag_source
+            .as_mut()
+            .filter(|drag| drag.source.wl_data_source == *proxy);
+
+        match event {
+            wl_data_source::Event::Send { mime_type, fd } => {
+                if let Some(write) = source
+                    .or(drag.map(|drag| &drag.source))
+                    .and_then(|source| source.send(&mime_type, fd))
+                {
+                    state.data_writes.push(write);
+                }
+            }
+            wl_data_source::Event::Action { dnd_action } => {
+                if let Some(drag) = drag {
+                    let action = dnd_action.into_result().unwrap_or(DndAction::empty());
+                    drag.action = action;
+                    state.events.push_back(Event::DragSourceAction { action });
+                }
+            }
+            wl_data_source::Event::DndFinished => {
+                if let Some(drag) = drag {
+                    let action = drag.action;
+                    state.drag_source = None;
+                    state.events.push_back(Event::DragSourceFinished { action });
+                }
+                proxy.destroy();
+            }
+            // another client took over the clipboard, or the drag ended without a drop
+            wl_data_source::Event::Cancelled => {
+                if source.is_some() {
+                    state.clipboard_source = None;
+                }
+                if drag.is_some() {
+                    state.drag_source = None;
+                    state.events.push_back(Event::DragSourceCancelled);
+                }
+                proxy.destroy();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zwp_primary_selection_device_manager_v1::ZwpPrimarySelectionDeviceManagerV1, ()>
+    for State
+{
+    fn event(
+        _state: &mut Self,
+        _proxy: &zwp_primary_selection_device_manager_v1::ZwpPrimarySelectionDeviceManagerV1,
+        _event: zwp_primary_selection_device_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<zwp_primary_selection_device_v1::ZwpPrimarySelectionDeviceV1, u32> for State {
+    fn event(
+        state: &mut Self,
+        _proxy: &zwp_primary_selection_device_v1::ZwpPrimarySelectionDeviceV1,
+        event: zwp_primary_selection_device_v1::Event,
+        id: &u32,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(device) = state
+            .seats
+            .iter_mut()
+            .find(|seat| seat.id == *id)
+            .and_then(|seat| seat.primary_selection.as_mut())
+        else {
+            return;
+        };
+
+        match event {
+            zwp_primary_selection_device_v1::Event::DataOffer { offer } => {
+                device.offers.push(PrimarySelectionOffer::new(offer));
+            }
+            zwp_primary_selection_device_v1::Event::Selection { id: offer } => {
+                let offer = offer.and_then(|offer| device.take_offer(&offer));
+                if let Some(old) = std::mem::replace(&mut device.selection, offer) {
+                    old.destroy();
+                }
+                state
+                    .events
+                    .push_back(Event::PrimarySelectionChanged { seat: *id });
+            }
+            _ => {}
+        }
+    }
+
+    wayland_client::event_created_child!(
+        State,
+        zwp_primary_selection_device_v1::ZwpPrimarySelectionDeviceV1,
+        [
+            zwp_primary_selection_device_v1::EVT_DATA_OFFER_OPCODE =>
+                (zwp_primary_selection_offer_v1::ZwpPrimarySelectionOfferV1, ()),
+        ]
+    );
+}
+
+impl Dispatch<zwp_primary_selection_offer_v1::ZwpPrimarySelectionOfferV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        proxy: &zwp_primary_selection_offer_v1::ZwpPrimarySelectionOfferV1,
+        event: zwp_primary_selection_offer_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        if let zwp_primary_selection_offer_v1::Event::Offer { mime_type } = event
+            && let Some(offer) = state
+                .seats
+                .iter_mut()
+                .filter_map(|seat| seat.primary_selection.as_mut())
+                .find_map(|device| device.offer_mut(proxy))
+        {
+            offer.mime_types.push(mime_type);
+        }
+    }
+}
+
+impl Dispatch<zwp_primary_selection_source_v1::ZwpPrimarySelectionSourceV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        proxy: &zwp_primary_selection_source_v1::ZwpPrimarySelectionSourceV1,
+        event: zwp_primary_selection_source_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let source = state
+            .primary_selection_source
+            .as_ref()
+            .filter(|source| source.zwp_primary_selection_source == *proxy);
+
+        match event {
+            zwp_primary_selection_source_v1::Event::Send { mime_type, fd } => {
+                if let Some(write) = source.and_then(|source| source.send(&mime_type, fd)) {
+                    state.data_writes.push(write);
+                }
+            }
+            // another client selected something
+            zwp_primary_selection_source_v1::Event::Cancelled => {
+                if source.is_some() {
+                    state.primary_selection_source = None;
+                }
+                proxy.destroy();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wl_touch::WlTouch, u32> for State {
+    fn event(
+        state: &mut Self,
+        _proxy: &wl_touch::WlTouch,
+        event: wl_touch::Event,
+        id: &u32,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(touch) = state
+            .seats
+            .iter_mut()
+            .find(|seat| seat.id == *id)
+            .and_then(|seat| seat.touch.as_mut())
+        else {
+            return;
+        };
+        let scale_of = |window: usize| {
+            state
+                .windows
+                .get(window)
+                .map(|window| window.scale_factor())
+                .unwrap_or(1.0)
+        };
+
+        match event {
+            wl_touch::Event::Down {
+                serial,
+                time,
+                surface,
+                id,
+                x,
+                y,
+            } => {
+                let Some(&window) = surface.data::<usize>() else {
+                    return;
+                };
+                let scale = scale_of(window);
+                touch.down_serial = serial;
+                touch.points.retain(|point| point.id != id);
+                touch.points.push(TouchPoint {
+                    id,
+                    window,
+                    position: (x, y),
+                });
+                touch.pending.push((
+                    window,
+                    TouchEvent::Down {
+                        id,
+                        position: (x, y),
+                        buffer_position: (x * scale, y * scale),
+                        serial,
+                        time,
+                    },
+                ));
+            }
+            wl_touch::Event::Up { serial, time, id } => {
+                let Some(pos) = touch.points.iter().position(|point| point.id == id) else {
+                    return;
+                };
+                let point = touch.points.remove(pos);
+                touch
+                    .pending
+                    .push((point.window, TouchEvent::Up { id, serial, time }));
+            }
+            wl_touch::Event::Motion { time, id, x, y } => {
+                let Some(point) = touch.point_mut(id) else {
+                    return;
+                };
+                point.position = (x, y);
+                let window = point.window;
+                let scale = scale_of(window);
+                touch.pending.push((
+                    window,
+                    TouchEvent::Motion {
+                        id,
+                        position: (x, y),
+                        buffer_position: (x * scale, y * scale),
+                        time,
+                    },
+                ));
+            }
+            wl_touch::Event::Shape { id, major, minor } => {
+                let Some(point) = touch.point(id) else {
+                    return;
+                };
+                touch
+                    .pending
+                    .push((point.window, TouchEvent::Shape { id, major, minor }));
+            }
+            wl_touch::Event::Orientation { id, orientation } => {
+                let Some(point) = touch.point(id) else {
+                    return;
+                };
+                touch
+                    .pending
+                    .push((point.window, TouchEvent::Orientation { id, orientation }));
+            }
+            wl_touch::Event::Cancel => {
+                // events of the current frame are void as well
+                touch.pending.clear();
+                touch.cancel();
+                for (window, events) in touch.take_frame() {
+                    state.events.push_back(Event::Touch {
+                        window,
+                        seat: *id,
+                        events,
+                    });
+                }
+            }
+            wl_touch::Event::Frame => {
+                for (window, events) in touch.take_frame() {
+                    state.events.push_back(Event::Touch {
+                        window,
+                        seat: *id,
+                        events,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wp_cursor_shape_manager_v1::WpCursorShapeManagerV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &wp_cursor_shape_manager_v1::WpCursorShapeManagerV1,
+        _event: wp_cursor_shape_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<wp_cursor_shape_device_v1::WpCursorShapeDeviceV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _proxy: &wp_cursor_shape_device_v1::WpCursorShapeDeviceV1,
+        _event: wp_cursor_shape_device_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+    }
+}
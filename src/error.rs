@@ -16,6 +16,7 @@ pub enum ClientErrorKind {
     Surface,
     XdgSurface,
     XdgTopLevel,
+    Clipboard,
 }
 
 impl ClientError {
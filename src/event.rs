@@ -0,0 +1,242 @@
+use super::gesture::GestureEvent;
+use super::keyboard::{KeyEvent, Modifiers};
+use super::pointer::PointerEvent;
+use super::tablet::{TabletPadEvent, TabletToolEvent};
+use super::timing::PresentationInfo;
+use super::touch::TouchEvent;
+use wayland_client::protocol::{wl_data_device_manager::DndAction, wl_seat};
+
+/// Events produced while dispatching, retrieved with `Client::poll_event`. `window` is the index
+/// returned by `Client::create_window`, `seat` is the `Seat::id` input events came from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// The compositor is ready for a new frame of `window`. `time` is the frame callback timestamp
+    /// in milliseconds, `interval` the time since the previous callback.
+    FrameReady {
+        window: usize,
+        time: u32,
+        interval: Option<u32>,
+    },
+    /// Frame number `frame` (see `Window::frame_count`) of `window` was shown on screen.
+    Presented {
+        window: usize,
+        frame: u64,
+        info: PresentationInfo,
+    },
+    /// Frame number `frame` of `window` was never shown, it got replaced or the surface was hidden.
+    Discarded {
+        window: usize,
+        frame: u64,
+    },
+    /// A new monitor finished announcing itself, see `Client::outputs`. `output` is `Output::id`.
+    OutputAdded {
+        output: u32,
+    },
+    /// Properties of a monitor changed, e.g. its mode, scale or position.
+    OutputChanged {
+        output: u32,
+    },
+    /// A monitor was unplugged.
+    OutputRemoved {
+        output: u32,
+    },
+    /// `window` started overlapping `output`, see `Window::outputs`.
+    WindowEnteredOutput {
+        window: usize,
+        output: u32,
+    },
+    /// `window` no longer overlaps `output`.
+    WindowLeftOutput {
+        window: usize,
+        output: u32,
+    },
+    /// The ratio between buffer pixels and logical pixels of `window` changed, its buffers were
+    /// reallocated at the new physical size. `factor` may be fractional, e.g. 1.25.
+    ScaleChanged {
+        window: usize,
+        factor: f64,
+    },
+    /// A new seat was announced. Its devices show up through `SeatCapabilitiesChanged`.
+    SeatAdded {
+        seat: u32,
+    },
+    /// The seat went away, its devices were released before.
+    SeatRemoved {
+        seat: u32,
+    },
+    /// Devices of the seat were plugged in or unplugged, e.g. `removed` contains
+    /// `Capability::Keyboard` when the last keyboard went away.
+    SeatCapabilitiesChanged {
+        seat: u32,
+        added: wl_seat::Capability,
+        removed: wl_seat::Capability,
+    },
+    /// The clipboard of the seat changed, see `Client::clipboard_mime_types`.
+    ClipboardChanged {
+        seat: u32,
+    },
+    /// The primary selection of the seat changed, see `Client::primary_selection_mime_types`.
+    PrimarySelectionChanged {
+        seat: u32,
+    },
+    /// A drag entered `window`. Answer with `Client::accept_drag` to take the drop. `position` is
+    /// in logical surface coordinates, `buffer_position` the same point in buffer pixels.
+    DragEnter {
+        window: usize,
+        seat: u32,
+        position: (f64, f64),
+        buffer_position: (f64, f64),
+        mime_types: Vec<String>,
+        source_actions: DndAction,
+    },
+    DragMotion {
+        window: usize,
+        seat: u32,
+        position: (f64, f64),
+        buffer_position: (f64, f64),
+        time: u32,
+    },
+    /// The drag left `window` without a drop.
+    DragLeave {
+        window: usize,
+        seat: u32,
+    },
+    /// The compositor picked another action for the drag over `window`, e.g. because the user
+    /// pressed a modifier.
+    DragActionChanged {
+        window: usize,
+        seat: u32,
+        action: DndAction,
+    },
+    /// The drag was dropped on `window`. Read the data with `Client::read_drop`, then call
+    /// `Client::finish_drop`. With `DndAction::Ask`, pick the action with `Client::accept_drag`
+    /// first.
+    Drop {
+        window: usize,
+        seat: u32,
+        action: DndAction,
+    },
+    /// The compositor picked another action for the drag started with `Client::start_drag`.
+    DragSourceAction {
+        action: DndAction,
+    },
+    /// The target took our drop. With `DndAction::Move` the data should be deleted now.
+    DragSourceFinished {
+        action: DndAction,
+    },
+    /// Our drag ended without a drop, or the target refused it.
+    DragSourceCancelled,
+    /// The keymap of the seat's keyboard couldn't be loaded, because libxkbcommon is missing or
+    /// the keymap is invalid. No key events are reported until a usable keymap arrives.
+    KeymapFailed {
+        seat: u32,
+    },
+    /// `window` gained keyboard focus.
+    KeyboardEnter {
+        window: usize,
+        seat: u32,
+    },
+    /// `window` lost keyboard focus, keys still held are not released explicitly.
+    KeyboardLeave {
+        window: usize,
+        seat: u32,
+    },
+    KeyPressed {
+        window: usize,
+        seat: u32,
+        key: KeyEvent,
+    },
+    KeyReleased {
+        window: usize,
+        seat: u32,
+        key: KeyEvent,
+    },
+    /// Text typed into `window`, sent after the `KeyPressed` that produced it.
+    Text {
+        window: usize,
+        seat: u32,
+        text: String,
+    },
+    ModifiersChanged {
+        window: usize,
+        seat: u32,
+        modifiers: Modifiers,
+    },
+    /// Pointer events of `window` that belong to one `wl_pointer.frame`, in the order received.
+    Pointer {
+        window: usize,
+        seat: u32,
+        events: Vec<PointerEvent>,
+    },
+    /// Touch events of `window` that belong to one `wl_touch.frame`, in the order received.
+    Touch {
+        window: usize,
+        seat: u32,
+        events: Vec<TouchEvent>,
+    },
+    /// A touchpad gesture over `window`.
+    Gesture {
+        window: usize,
+        seat: u32,
+        event: GestureEvent,
+    },
+    /// The pointer lock asked for with `Window::lock_pointer` became active.
+    PointerLocked {
+        window: usize,
+        seat: u32,
+    },
+    PointerUnlocked {
+        window: usize,
+        seat: u32,
+    },
+    /// The confinement asked for with `Window::confine_pointer` became active.
+    PointerConfined {
+        window: usize,
+        seat: u32,
+    },
+    PointerUnconfined {
+        window: usize,
+        seat: u32,
+    },
+    /// A tablet finished announcing itself, see `Client::tablets`.
+    TabletAdded {
+        seat: u32,
+        tablet: u32,
+    },
+    TabletRemoved {
+        seat: u32,
+        tablet: u32,
+    },
+    /// A tool was used for the first time, see `Client::tablet_tools`.
+    TabletToolAdded {
+        seat: u32,
+        tool: u32,
+    },
+    TabletToolRemoved {
+        seat: u32,
+        tool: u32,
+    },
+    TabletPadAdded {
+        seat: u32,
+        pad: u32,
+    },
+    TabletPadRemoved {
+        seat: u32,
+        pad: u32,
+    },
+    /// Events of `tool` over `window` that belong to one `zwp_tablet_tool_v2.frame`, in the order
+    /// received. `time` is in milliseconds.
+    TabletTool {
+        window: usize,
+        seat: u32,
+        tool: u32,
+        events: Vec<TabletToolEvent>,
+        time: u32,
+    },
+    TabletPad {
+        window: usize,
+        seat: u32,
+        pad: u32,
+        event: TabletPadEvent,
+    },
+}
@@ -0,0 +1,72 @@
+use wayland_protocols::wp::pointer_gestures::zv1::client::{
+    zwp_pointer_gesture_hold_v1, zwp_pointer_gesture_pinch_v1, zwp_pointer_gesture_swipe_v1,
+};
+
+/// Touchpad gestures from `zwp_pointer_gestures_v1`. Every begin is followed by exactly one end
+/// of the same kind, `cancelled` is set when the gesture should be undone.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GestureEvent {
+    SwipeBegin {
+        fingers: u32,
+        serial: u32,
+        time: u32,
+    },
+    /// `delta` is the motion since the last update in logical surface coordinates.
+    SwipeUpdate { delta: (f64, f64), time: u32 },
+    SwipeEnd {
+        cancelled: bool,
+        serial: u32,
+        time: u32,
+    },
+    PinchBegin {
+        fingers: u32,
+        serial: u32,
+        time: u32,
+    },
+    /// `scale` is relative to the finger distance at the start of the pinch, `rotation` is in
+    /// degrees clockwise relative to the previous update.
+    PinchUpdate {
+        delta: (f64, f64),
+        scale: f64,
+        rotation: f64,
+        time: u32,
+    },
+    PinchEnd {
+        cancelled: bool,
+        serial: u32,
+        time: u32,
+    },
+    /// Fingers rest on the touchpad without moving, e.g. to stop kinetic scrolling.
+    HoldBegin {
+        fingers: u32,
+        serial: u32,
+        time: u32,
+    },
+    HoldEnd {
+        cancelled: bool,
+        serial: u32,
+        time: u32,
+    },
+}
+
+/// Gesture objects of one `wl_pointer`.
+#[derive(Debug)]
+pub struct Gestures {
+    pub swipe: zwp_pointer_gesture_swipe_v1::ZwpPointerGestureSwipeV1,
+    pub pinch: zwp_pointer_gesture_pinch_v1::ZwpPointerGesturePinchV1,
+    /// Only available from version 3 of the manager.
+    pub hold: Option<zwp_pointer_gesture_hold_v1::ZwpPointerGestureHoldV1>,
+
+    /// Window the running gesture started on, updates and ends carry no surface.
+    pub focus: Option<usize>,
+}
+
+impl Gestures {
+    pub fn destroy(&self) {
+        self.swipe.destroy();
+        self.pinch.destroy();
+        if let Some(hold) = &self.hold {
+            hold.destroy();
+        }
+    }
+}
@@ -0,0 +1,558 @@
+use std::env;
+use std::ffi::{CStr, CString};
+use std::fs::File;
+use std::os::fd::OwnedFd;
+use std::os::unix::fs::FileExt;
+use std::ptr;
+use std::time::{Duration, Instant};
+use wayland_client::protocol::wl_keyboard;
+use xkbcommon_dl::{
+    XKB_MOD_NAME_ALT, XKB_MOD_NAME_CAPS, XKB_MOD_NAME_CTRL, XKB_MOD_NAME_LOGO, XKB_MOD_NAME_NUM,
+    XKB_MOD_NAME_SHIFT, xkb_compose_compile_flags, xkb_compose_feed_result, xkb_compose_state,
+    xkb_compose_state_flags, xkb_compose_status, xkb_compose_table, xkb_context, xkb_context_flags,
+    xkb_keymap, xkb_keymap_compile_flags, xkb_keymap_format, xkb_state, xkb_state_component,
+    xkbcommon_compose_option, xkbcommon_option,
+};
+
+/// Evdev keycodes are offset by 8 in xkb.
+const XKB_KEYCODE_OFFSET: u32 = 8;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub logo: bool,
+    pub caps_lock: bool,
+    pub num_lock: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyEvent {
+    /// Evdev keycode as sent by the compositor.
+    pub keycode: u32,
+    /// Keysym after applying the keymap and modifiers, compare with `keysyms`. 0 when no keymap
+    /// is available.
+    pub keysym: u32,
+    /// UTF-8 the key produces with the current modifiers, if any.
+    pub text: Option<String>,
+    pub modifiers: Modifiers,
+    /// Synthesised by client-side key repeat rather than sent by the compositor.
+    pub repeat: bool,
+    /// Set when the key took part in a dead-key or Compose sequence.
+    pub compose: Option<ComposeStatus>,
+
+    pub serial: u32,
+    pub time: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComposeStatus {
+    /// The key started or continued a sequence, it produces no text yet.
+    Composing,
+    /// The sequence didn't match anything and was dropped.
+    Cancelled,
+    /// The sequence completed, `KeyEvent::text` holds the composed text.
+    Finished,
+}
+
+/// Key repeat settings from `wl_keyboard.repeat_info`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RepeatInfo {
+    /// Repeats per second, 0 disables repeat.
+    pub rate: i32,
+    /// Delay before the first repeat, in milliseconds.
+    pub delay: i32,
+}
+
+impl Default for RepeatInfo {
+    // used until the compositor sends its settings
+    fn default() -> Self {
+        RepeatInfo {
+            rate: 25,
+            delay: 600,
+        }
+    }
+}
+
+/// The key currently being repeated.
+#[derive(Debug, Clone)]
+pub struct KeyRepeat {
+    pub window: usize,
+    pub keycode: u32,
+    pub serial: u32,
+    /// Compositor timestamp of the press, repeat timestamps are derived from it.
+    pub time: u32,
+    pub started: Instant,
+    pub next: Instant,
+}
+
+/// Compiled keymap and its state. Needs libxkbcommon at runtime, it is loaded on first use.
+#[derive(Debug)]
+pub struct Xkb {
+    context: *mut xkb_context,
+    keymap: *mut xkb_keymap,
+    state: *mut xkb_state,
+
+    // kept private so the compose objects, which share the context, can't outlive or leave it
+    compose: Option<Compose>,
+}
+
+// SAFETY: every xkbcommon object here is created for this `Xkb` alone, the compose table included,
+// and none of the pointers are handed out, so they only move between threads all together.
+unsafe impl Send for Xkb {}
+
+/// Compose table for the user's locale together with the sequence in progress.
+#[derive(Debug)]
+pub struct Compose {
+    table: *mut xkb_compose_table,
+    state: *mut xkb_compose_state,
+}
+
+impl Compose {
+    /// Loads the compose table of the locale from `LC_ALL`, `LC_CTYPE` or `LANG`, the same
+    /// lookup order the C library uses.
+    fn new(context: *mut xkb_context) -> Option<Self> {
+        let compose = xkbcommon_compose_option()?;
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_else(|| "C".to_string());
+        let locale = CString::new(locale).ok()?;
+
+        unsafe {
+            let table = (compose.xkb_compose_table_new_from_locale)(
+                context,
+                locale.as_ptr(),
+                xkb_compose_compile_flags::XKB_COMPOSE_COMPILE_NO_FLAGS,
+            );
+            if table.is_null() {
+                return None;
+            }
+
+            let state = (compose.xkb_compose_state_new)(
+                table,
+                xkb_compose_state_flags::XKB_COMPOSE_STATE_NO_FLAGS,
+            );
+            if state.is_null() {
+                (compose.xkb_compose_table_unref)(table);
+                return None;
+            }
+
+            Some(Compose { table, state })
+        }
+    }
+
+    /// Feeds a pressed keysym into the sequence. Returns `None` when the key has nothing to do
+    /// with composing, otherwise the new status and, once finished, the composed text.
+    pub fn feed(&mut self, keysym: u32) -> Option<(ComposeStatus, Option<String>)> {
+        let compose = xkbcommon_compose_option()?;
+
+        unsafe {
+            let result = (compose.xkb_compose_state_feed)(self.state, keysym);
+            if result == xkb_compose_feed_result::XKB_COMPOSE_FEED_IGNORED {
+                return None;
+            }
+
+            match (compose.xkb_compose_state_get_status)(self.state) {
+                xkb_compose_status::XKB_COMPOSE_NOTHING => None,
+                xkb_compose_status::XKB_COMPOSE_COMPOSING => Some((ComposeStatus::Composing, None)),
+                xkb_compose_status::XKB_COMPOSE_CANCELLED => {
+                    (compose.xkb_compose_state_reset)(self.state);
+                    Some((ComposeStatus::Cancelled, None))
+                }
+                xkb_compose_status::XKB_COMPOSE_COMPOSED => {
+                    let mut buffer = [0u8; 64];
+                    (compose.xkb_compose_state_get_utf8)(
+                        self.state,
+                        buffer.as_mut_ptr().cast(),
+                        buffer.len(),
+                    );
+                    (compose.xkb_compose_state_reset)(self.state);
+                    Some((ComposeStatus::Finished, utf8_text(&buffer)))
+                }
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        if let Some(compose) = xkbcommon_compose_option() {
+            unsafe { (compose.xkb_compose_state_reset)(self.state) };
+        }
+    }
+}
+
+impl Drop for Compose {
+    fn drop(&mut self) {
+        if let Some(compose) = xkbcommon_compose_option() {
+            unsafe {
+                (compose.xkb_compose_state_unref)(self.state);
+                (compose.xkb_compose_table_unref)(self.table);
+            }
+        }
+    }
+}
+
+impl Xkb {
+    /// Compiles the keymap the compositor sent in `wl_keyboard.keymap`.
+    pub fn from_fd(fd: OwnedFd, size: u32) -> Option<Self> {
+        let xkb = xkbcommon_option()?;
+
+        // pread keeps the file offset the compositor may share with other clients untouched
+        let mut data = vec![0; size as usize];
+        File::from(fd).read_exact_at(&mut data, 0).ok()?;
+        let end = data
+            .iter()
+            .position(|&byte| byte == 0)
+            .unwrap_or(data.len());
+        data.truncate(end);
+        let keymap_string = CString::new(data).ok()?;
+
+        unsafe {
+            let context = (xkb.xkb_context_new)(xkb_context_flags::XKB_CONTEXT_NO_FLAGS);
+            if context.is_null() {
+                return None;
+            }
+
+            let keymap = (xkb.xkb_keymap_new_from_string)(
+                context,
+                keymap_string.as_ptr(),
+                xkb_keymap_format::XKB_KEYMAP_FORMAT_TEXT_V1,
+                xkb_keymap_compile_flags::XKB_KEYMAP_COMPILE_NO_FLAGS,
+            );
+            if keymap.is_null() {
+                (xkb.xkb_context_unref)(context);
+                return None;
+            }
+
+            let state = (xkb.xkb_state_new)(keymap);
+            if state.is_null() {
+                (xkb.xkb_keymap_unref)(keymap);
+                (xkb.xkb_context_unref)(context);
+                return None;
+            }
+
+            Some(Xkb {
+                context,
+                keymap,
+                state,
+                compose: Compose::new(context),
+            })
+        }
+    }
+
+    pub fn update_modifiers(&mut self, depressed: u32, latched: u32, locked: u32, group: u32) {
+        let Some(xkb) = xkbcommon_option() else {
+            return;
+        };
+        unsafe {
+            (xkb.xkb_state_update_mask)(self.state, depressed, latched, locked, 0, 0, group);
+        }
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        let Some(xkb) = xkbcommon_option() else {
+            return Modifiers::default();
+        };
+        let is_active = |name: &[u8]| unsafe {
+            (xkb.xkb_state_mod_name_is_active)(
+                self.state,
+                name.as_ptr().cast(),
+                xkb_state_component::XKB_STATE_MODS_EFFECTIVE,
+            ) > 0
+        };
+
+        Modifiers {
+            shift: is_active(XKB_MOD_NAME_SHIFT),
+            ctrl: is_active(XKB_MOD_NAME_CTRL),
+            alt: is_active(XKB_MOD_NAME_ALT),
+            logo: is_active(XKB_MOD_NAME_LOGO),
+            caps_lock: is_active(XKB_MOD_NAME_CAPS),
+            num_lock: is_active(XKB_MOD_NAME_NUM),
+        }
+    }
+
+    pub fn key_repeats(&self, keycode: u32) -> bool {
+        match xkbcommon_option() {
+            Some(xkb) => unsafe {
+                (xkb.xkb_keymap_key_repeats)(self.keymap, keycode + XKB_KEYCODE_OFFSET) != 0
+            },
+            None => false,
+        }
+    }
+
+    pub fn keysym(&self, keycode: u32) -> u32 {
+        match xkbcommon_option() {
+            Some(xkb) => unsafe {
+                (xkb.xkb_state_key_get_one_sym)(self.state, keycode + XKB_KEYCODE_OFFSET)
+            },
+            None => 0,
+        }
+    }
+
+    /// Text the key produces, `None` for keys that only produce control characters.
+    pub fn text(&self, keycode: u32) -> Option<String> {
+        let xkb = xkbcommon_option()?;
+        let keycode = keycode + XKB_KEYCODE_OFFSET;
+
+        unsafe {
+            let size = (xkb.xkb_state_key_get_utf8)(self.state, keycode, ptr::null_mut(), 0);
+            if size <= 0 {
+                return None;
+            }
+
+            let mut buffer = vec![0u8; size as usize + 1];
+            (xkb.xkb_state_key_get_utf8)(
+                self.state,
+                keycode,
+                buffer.as_mut_ptr().cast(),
+                buffer.len(),
+            );
+            utf8_text(&buffer)
+        }
+    }
+}
+
+impl Drop for Xkb {
+    fn drop(&mut self) {
+        // the compose table references the context, release it first
+        self.compose = None;
+        if let Some(xkb) = xkbcommon_option() {
+            unsafe {
+                (xkb.xkb_state_unref)(self.state);
+                (xkb.xkb_keymap_unref)(self.keymap);
+                (xkb.xkb_context_unref)(self.context);
+            }
+        }
+    }
+}
+
+/// Turns a NUL terminated buffer filled by xkbcommon into text, dropping control characters.
+pub fn utf8_text(buffer: &[u8]) -> Option<String> {
+    let text = CStr::from_bytes_until_nul(buffer).ok()?.to_str().ok()?;
+    if text.is_empty() || text.chars().all(char::is_control) {
+        return None;
+    }
+    Some(text.to_string())
+}
+
+#[derive(Debug)]
+pub struct Keyboard {
+    pub wl_keyboard: wl_keyboard::WlKeyboard,
+    pub xkb: Option<Xkb>,
+
+    /// Window with keyboard focus.
+    pub focus: Option<usize>,
+    pub modifiers: Modifiers,
+    /// Serial of the last enter or key event, needed by requests such as clipboard ones.
+    pub serial: u32,
+
+    pub repeat_info: RepeatInfo,
+    pub repeat: Option<KeyRepeat>,
+}
+
+impl Keyboard {
+    pub fn new(wl_keyboard: wl_keyboard::WlKeyboard) -> Self {
+        Keyboard {
+            wl_keyboard,
+            xkb: None,
+            focus: None,
+            modifiers: Modifiers::default(),
+            serial: 0,
+            repeat_info: RepeatInfo::default(),
+            repeat: None,
+        }
+    }
+
+    /// Builds the event for a key, with the keysym and text from the current xkb state.
+    pub fn key_event(&self, keycode: u32, serial: u32, time: u32) -> KeyEvent {
+        KeyEvent {
+            keycode,
+            keysym: self
+                .xkb
+                .as_ref()
+                .map(|xkb| xkb.keysym(keycode))
+                .unwrap_or(0),
+            text: self.xkb.as_ref().and_then(|xkb| xkb.text(keycode)),
+            modifiers: self.modifiers,
+            repeat: false,
+            compose: None,
+            serial,
+            time,
+        }
+    }
+
+    /// Builds the event for a freshly pressed key, running it through the compose state so dead
+    /// keys and Compose sequences produce the right text.
+    pub fn press_event(&mut self, keycode: u32, serial: u32, time: u32) -> KeyEvent {
+        let mut key = self.key_event(keycode, serial, time);
+
+        let compose = self.xkb.as_mut().and_then(|xkb| xkb.compose.as_mut());
+        if let Some((status, text)) = compose.and_then(|compose| compose.feed(key.keysym)) {
+            key.compose = Some(status);
+            key.text = text;
+        }
+
+        key
+    }
+
+    pub fn reset_compose(&mut self) {
+        if let Some(compose) = self.xkb.as_mut().and_then(|xkb| xkb.compose.as_mut()) {
+            compose.reset();
+        }
+    }
+
+    /// Starts repeating `keycode` after the repeat delay, replacing any key repeated before.
+    pub fn start_repeat(&mut self, window: usize, keycode: u32, serial: u32, time: u32) {
+        self.repeat = None;
+
+        let repeats = self
+            .xkb
+            .as_ref()
+            .is_some_and(|xkb| xkb.key_repeats(keycode));
+        if !repeats || self.repeat_info.rate <= 0 {
+            return;
+        }
+
+        let now = Instant::now();
+        self.repeat = Some(KeyRepeat {
+            window,
+            keycode,
+            serial,
+            time,
+            started: now,
+            next: now + Duration::from_millis(self.repeat_info.delay.max(0) as u64),
+        });
+    }
+
+    pub fn stop_repeat(&mut self, keycode: u32) {
+        if self
+            .repeat
+            .as_ref()
+            .is_some_and(|repeat| repeat.keycode == keycode)
+        {
+            self.repeat = None;
+        }
+    }
+
+    /// Produces the repeated key if it is due, and schedules the next one.
+    pub fn poll_repeat(&mut self, now: Instant) -> Option<(usize, KeyEvent)> {
+        let repeat = self.repeat.as_ref()?;
+        if repeat.next > now {
+            return None;
+        }
+
+        let interval = Duration::from_secs_f64(1.0 / self.repeat_info.rate.max(1) as f64);
+        let elapsed = repeat.next.duration_since(repeat.started).as_millis() as u32;
+        let (window, keycode, serial) = (repeat.window, repeat.keycode, repeat.serial);
+        let time = repeat.time.wrapping_add(elapsed);
+
+        let mut key = self.key_event(keycode, serial, time);
+        key.repeat = true;
+
+        let repeat = self.repeat.as_mut()?;
+        repeat.next += interval;
+        // a stalled event loop shouldn't cause a burst of repeats afterwards
+        if repeat.next < now {
+            repeat.next = now + interval;
+        }
+
+        Some((window, key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::inert_backend;
+    use super::*;
+    use wayland_client::Proxy;
+
+    fn inert_keyboard() -> Keyboard {
+        Keyboard::new(wl_keyboard::WlKeyboard::inert(inert_backend()))
+    }
+
+    // start_repeat needs a keymap, so the tests set up the repeat the way it would
+    fn repeating(keyboard: &mut Keyboard, started: Instant) {
+        keyboard.repeat = Some(KeyRepeat {
+            window: 3,
+            keycode: 30,
+            serial: 7,
+            time: 1000,
+            started,
+            next: started + Duration::from_millis(keyboard.repeat_info.delay as u64),
+        });
+    }
+
+    #[test]
+    fn repeats_after_the_delay_then_at_the_rate() {
+        let mut keyboard = inert_keyboard();
+        keyboard.repeat_info = RepeatInfo {
+            rate: 25,
+            delay: 600,
+        };
+        let started = Instant::now();
+        repeating(&mut keyboard, started);
+
+        assert!(
+            keyboard
+                .poll_repeat(started + Duration::from_millis(599))
+                .is_none()
+        );
+
+        let (window, key) = keyboard
+            .poll_repeat(started + Duration::from_millis(600))
+            .unwrap();
+        assert_eq!(window, 3);
+        assert_eq!((key.keycode, key.serial, key.time), (30, 7, 1600));
+        assert!(key.repeat);
+        assert_eq!(
+            keyboard.repeat.as_ref().unwrap().next,
+            started + Duration::from_millis(640)
+        );
+
+        assert!(
+            keyboard
+                .poll_repeat(started + Duration::from_millis(639))
+                .is_none()
+        );
+        let (_, key) = keyboard
+            .poll_repeat(started + Duration::from_millis(645))
+            .unwrap();
+        // timestamps follow the schedule, not the moment the loop got around to it
+        assert_eq!(key.time, 1640);
+    }
+
+    #[test]
+    fn stalled_loop_does_not_burst() {
+        let mut keyboard = inert_keyboard();
+        let started = Instant::now();
+        repeating(&mut keyboard, started);
+
+        let late = started + Duration::from_secs(5);
+        assert!(keyboard.poll_repeat(late).is_some());
+        assert_eq!(
+            keyboard.repeat.as_ref().unwrap().next,
+            late + Duration::from_millis(40)
+        );
+        assert!(keyboard.poll_repeat(late).is_none());
+    }
+
+    #[test]
+    fn releasing_another_key_keeps_repeating() {
+        let mut keyboard = inert_keyboard();
+        repeating(&mut keyboard, Instant::now());
+
+        keyboard.stop_repeat(31);
+        assert!(keyboard.repeat.is_some());
+        keyboard.stop_repeat(30);
+        assert!(keyboard.repeat.is_none());
+    }
+
+    #[test]
+    fn no_repeat_without_a_keymap() {
+        let mut keyboard = inert_keyboard();
+        keyboard.start_repeat(0, 30, 1, 0);
+        assert!(keyboard.repeat.is_none());
+    }
+}
@@ -1,13 +1,69 @@
+mod canvas;
 mod client;
+mod constraint;
+mod cursor;
+mod damage;
+mod data_device;
 mod dispatch;
 mod error;
+mod event;
+mod gesture;
+mod keyboard;
+mod output;
+mod pointer;
+mod primary_selection;
+mod seat;
+mod shm;
+mod tablet;
+mod timing;
+mod touch;
+
+#[cfg(test)]
+mod testing;
 
 const DEFAULT_PIXEL_FORMAT: wayland_client::protocol::wl_shm::Format =
     wayland_client::protocol::wl_shm::Format::Argb8888;
 
+pub use canvas::Canvas;
 pub use client::Client;
+pub use client::ControlFlow;
+pub use client::ObjectCount;
+pub use client::ObjectStats;
 pub use client::State;
 pub use client::Window;
+pub use constraint::ConstraintKind;
+pub use constraint::PointerConstraint;
+pub use cursor::CursorIcon;
+pub use damage::Rect;
+pub use data_device::DataRead;
 
 pub use error::ClientError;
 pub use error::ClientErrorKind;
+
+pub use event::Event;
+pub use gesture::GestureEvent;
+pub use keyboard::ComposeStatus;
+pub use keyboard::KeyEvent;
+pub use keyboard::Modifiers;
+pub use keyboard::RepeatInfo;
+pub use output::Mode;
+pub use output::Output;
+pub use pointer::AxisFrame;
+pub use pointer::AxisValue;
+pub use pointer::PointerEvent;
+pub use pointer::{BUTTON_LEFT, BUTTON_MIDDLE, BUTTON_RIGHT};
+pub use seat::Seat;
+pub use tablet::Tablet;
+pub use tablet::TabletPad;
+pub use tablet::TabletPadEvent;
+pub use tablet::TabletTool;
+pub use tablet::TabletToolEvent;
+pub use timing::FrameStats;
+pub use timing::PresentationInfo;
+pub use touch::TouchEvent;
+pub use touch::TouchPoint;
+
+/// Drag and drop actions, see `Client::start_drag` and `Client::accept_drag`.
+pub use wayland_client::protocol::wl_data_device_manager::DndAction;
+/// Keysym constants to compare `KeyEvent::keysym` against.
+pub use xkbcommon_dl::keysyms;
@@ -0,0 +1,170 @@
+use wayland_client::Proxy;
+use wayland_client::protocol::wl_output;
+use wayland_protocols::xdg::xdg_output::zv1::client::zxdg_output_v1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mode {
+    pub width: i32,
+    pub height: i32,
+    /// Refresh rate in mHz.
+    pub refresh: i32,
+
+    pub current: bool,
+    pub preferred: bool,
+}
+
+/// A monitor announced through `wl_output`. `id` is the registry name of the global, it stays
+/// the same for as long as the output is plugged in.
+#[derive(Debug)]
+pub struct Output {
+    pub id: u32,
+    pub wl_output: wl_output::WlOutput,
+    pub xdg_output: Option<zxdg_output_v1::ZxdgOutputV1>,
+
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub make: String,
+    pub model: String,
+
+    pub modes: Vec<Mode>,
+    /// Physical size in millimeters.
+    pub physical_width: i32,
+    pub physical_height: i32,
+    pub subpixel: wl_output::Subpixel,
+    pub transform: wl_output::Transform,
+    pub scale: i32,
+
+    /// Position and size in the global compositor space. Without `zxdg_output_manager_v1` the
+    /// position comes from `wl_output.geometry` and the size stays unknown.
+    pub logical_x: i32,
+    pub logical_y: i32,
+    pub logical_width: Option<i32>,
+    pub logical_height: Option<i32>,
+
+    /// Set once `zxdg_output_v1.logical_position` arrived.
+    pub logical_position_known: bool,
+    /// Set once the first `wl_output.done` arrived.
+    pub done: bool,
+    /// Set once the output finished announcing itself and `OutputAdded` was emitted, before that
+    /// the fields are incomplete.
+    pub ready: bool,
+}
+
+impl Output {
+    pub fn new(id: u32, wl_output: wl_output::WlOutput) -> Self {
+        Output {
+            id,
+            wl_output,
+            xdg_output: None,
+            name: None,
+            description: None,
+            make: String::new(),
+            model: String::new(),
+            modes: Vec::new(),
+            physical_width: 0,
+            physical_height: 0,
+            subpixel: wl_output::Subpixel::Unknown,
+            transform: wl_output::Transform::Normal,
+            scale: 1,
+            logical_x: 0,
+            logical_y: 0,
+            logical_width: None,
+            logical_height: None,
+            logical_position_known: false,
+            done: false,
+            ready: false,
+        }
+    }
+
+    /// True once `wl_output.done` arrived, along with the logical position and size when
+    /// xdg-output is in use.
+    pub fn is_complete(&self) -> bool {
+        self.done
+            && (self.xdg_output.is_none()
+                || (self.logical_position_known && self.logical_width.is_some()))
+    }
+
+    pub fn current_mode(&self) -> Option<&Mode> {
+        self.modes.iter().find(|mode| mode.current)
+    }
+
+    /// Records a `wl_output.mode` event, replacing an earlier entry with the same resolution and
+    /// refresh rate.
+    pub fn update_mode(&mut self, mode: Mode) {
+        if mode.current {
+            for other in &mut self.modes {
+                other.current = false;
+            }
+        }
+
+        match self.modes.iter_mut().find(|other| {
+            other.width == mode.width
+                && other.height == mode.height
+                && other.refresh == mode.refresh
+        }) {
+            Some(other) => *other = mode,
+            None => self.modes.push(mode),
+        }
+    }
+
+    /// Destroys the protocol objects of an output whose global went away.
+    pub fn destroy(&self) {
+        if let Some(xdg_output) = &self.xdg_output {
+            xdg_output.destroy();
+        }
+        if self.wl_output.version() >= 3 {
+            self.wl_output.release();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::inert_backend;
+    use super::*;
+
+    fn inert_output() -> Output {
+        Output::new(1, wl_output::WlOutput::inert(inert_backend()))
+    }
+
+    #[test]
+    fn complete_after_done_without_xdg_output() {
+        let mut output = inert_output();
+        assert!(!output.is_complete());
+        output.done = true;
+        assert!(output.is_complete());
+    }
+
+    #[test]
+    fn xdg_output_waits_for_logical_geometry() {
+        let mut output = inert_output();
+        let backend = output.wl_output.backend().clone();
+        output.xdg_output = Some(zxdg_output_v1::ZxdgOutputV1::inert(backend));
+
+        output.done = true;
+        assert!(!output.is_complete());
+        output.logical_position_known = true;
+        assert!(!output.is_complete());
+        output.logical_width = Some(1920);
+        output.logical_height = Some(1080);
+        assert!(output.is_complete());
+    }
+
+    #[test]
+    fn mode_updates_replace_the_current_one() {
+        let mut output = inert_output();
+        let mode = |width, current| Mode {
+            width,
+            height: 1080,
+            refresh: 60000,
+            current,
+            preferred: false,
+        };
+        output.update_mode(mode(1920, true));
+        output.update_mode(mode(1280, true));
+        output.update_mode(mode(1920, false));
+
+        assert_eq!(output.modes.len(), 2);
+        assert_eq!(output.current_mode().map(|mode| mode.width), Some(1280));
+    }
+}
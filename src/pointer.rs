@@ -0,0 +1,231 @@
+use super::constraint::AppliedConstraint;
+use super::cursor::{CursorIcon, CursorState};
+use super::gesture::Gestures;
+use wayland_client::WEnum;
+use wayland_client::protocol::wl_pointer;
+use wayland_protocols::wp::cursor_shape::v1::client::wp_cursor_shape_device_v1;
+use wayland_protocols::wp::relative_pointer::zv1::client::zwp_relative_pointer_v1;
+
+/// Linux evdev button codes, as used by `PointerEvent::Button`.
+pub const BUTTON_LEFT: u32 = 0x110;
+pub const BUTTON_RIGHT: u32 = 0x111;
+pub const BUTTON_MIDDLE: u32 = 0x112;
+
+/// Scroll along one axis within a single pointer frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct AxisValue {
+    /// Motion in surface-local coordinates, as a touchpad or a wheel step would scroll.
+    pub absolute: f64,
+    /// High-resolution wheel steps, 120 per detent. 0 for continuous sources.
+    pub value120: i32,
+    /// Scrolling on this axis stopped, kinetic scrolling may start here.
+    pub stop: bool,
+}
+
+impl AxisValue {
+    fn is_empty(&self) -> bool {
+        self.absolute == 0.0 && self.value120 == 0 && !self.stop
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AxisFrame {
+    pub source: Option<wl_pointer::AxisSource>,
+    pub horizontal: AxisValue,
+    pub vertical: AxisValue,
+    pub time: u32,
+}
+
+impl AxisFrame {
+    /// The values of `axis`, `None` for axes this version doesn't know about.
+    pub fn axis(&mut self, axis: WEnum<wl_pointer::Axis>) -> Option<&mut AxisValue> {
+        match axis.into_result() {
+            Ok(wl_pointer::Axis::HorizontalScroll) => Some(&mut self.horizontal),
+            Ok(wl_pointer::Axis::VerticalScroll) => Some(&mut self.vertical),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PointerEvent {
+    /// The pointer entered the window. Positions are in logical surface coordinates,
+    /// `buffer_position` is the same point in buffer pixels.
+    Enter {
+        position: (f64, f64),
+        buffer_position: (f64, f64),
+        serial: u32,
+    },
+    Leave {
+        serial: u32,
+    },
+    Motion {
+        position: (f64, f64),
+        buffer_position: (f64, f64),
+        time: u32,
+    },
+    /// `button` is an evdev code such as `BUTTON_LEFT`.
+    Button {
+        button: u32,
+        pressed: bool,
+        serial: u32,
+        time: u32,
+    },
+    Axis(AxisFrame),
+    /// Motion not limited by screen edges or constraints, reported even while the pointer is
+    /// locked. `delta` has pointer acceleration applied, `delta_unaccelerated` is the raw device
+    /// motion. `utime` is in microseconds.
+    RelativeMotion {
+        delta: (f64, f64),
+        delta_unaccelerated: (f64, f64),
+        utime: u64,
+    },
+}
+
+#[derive(Debug)]
+pub struct Pointer {
+    pub wl_pointer: wl_pointer::WlPointer,
+
+    /// Window under the pointer.
+    pub focus: Option<usize>,
+    pub position: (f64, f64),
+    /// Serial of the last enter, needed to set the cursor image.
+    pub enter_serial: u32,
+    /// Serial of the last button event, needed for moves, resizes and drags.
+    pub button_serial: u32,
+
+    /// Events received since the last `wl_pointer.frame`, with the window they belong to.
+    pub pending: Vec<(usize, PointerEvent)>,
+    pub pending_axis: Option<AxisFrame>,
+
+    /// Cursor surface, created the first time the pointer enters a window.
+    pub cursor: Option<CursorState>,
+    /// Server-side cursor shapes, used instead of the theme when the compositor supports them.
+    pub cursor_shape_device: Option<wp_cursor_shape_device_v1::WpCursorShapeDeviceV1>,
+    /// Window, icon and scale the last shape was set for.
+    pub cursor_shape_applied: Option<(usize, CursorIcon, i32)>,
+
+    /// Touchpad gestures, when the compositor supports `zwp_pointer_gestures_v1`.
+    pub gestures: Option<Gestures>,
+
+    /// Relative motion source, when the compositor supports `zwp_relative_pointer_manager_v1`.
+    pub relative_pointer: Option<zwp_relative_pointer_v1::ZwpRelativePointerV1>,
+    /// Locks and confinements currently set up, at most one per window.
+    pub constraints: Vec<AppliedConstraint>,
+}
+
+impl Pointer {
+    pub fn new(wl_pointer: wl_pointer::WlPointer) -> Self {
+        Pointer {
+            wl_pointer,
+            focus: None,
+            position: (0.0, 0.0),
+            enter_serial: 0,
+            button_serial: 0,
+            pending: Vec::new(),
+            pending_axis: None,
+            cursor: None,
+            cursor_shape_device: None,
+            cursor_shape_applied: None,
+            gestures: None,
+            relative_pointer: None,
+            constraints: Vec::new(),
+        }
+    }
+
+    /// Axis state of the current frame, created on first use.
+    pub fn axis_frame(&mut self, time: Option<u32>) -> &mut AxisFrame {
+        let frame = self.pending_axis.get_or_insert(AxisFrame {
+            source: None,
+            horizontal: AxisValue::default(),
+            vertical: AxisValue::default(),
+            time: 0,
+        });
+        if let Some(time) = time {
+            frame.time = time;
+        }
+        frame
+    }
+
+    /// Ends the current frame, returning its events grouped by window in order.
+    pub fn take_frame(&mut self) -> Vec<(usize, Vec<PointerEvent>)> {
+        if let Some(axis) = self.pending_axis.take()
+            && let Some(window) = self.focus
+            && !(axis.horizontal.is_empty() && axis.vertical.is_empty())
+        {
+            self.pending.push((window, PointerEvent::Axis(axis)));
+        }
+
+        let mut frames: Vec<(usize, Vec<PointerEvent>)> = Vec::new();
+        for (window, event) in self.pending.drain(..) {
+            match frames.last_mut() {
+                Some((last, events)) if *last == window => events.push(event),
+                _ => frames.push((window, vec![event])),
+            }
+        }
+        frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::inert_backend;
+    use super::*;
+    use wayland_client::Proxy;
+
+    const VERTICAL: WEnum<wl_pointer::Axis> = WEnum::Value(wl_pointer::Axis::VerticalScroll);
+    const HORIZONTAL: WEnum<wl_pointer::Axis> = WEnum::Value(wl_pointer::Axis::HorizontalScroll);
+
+    fn inert_pointer() -> Pointer {
+        let mut pointer = Pointer::new(wl_pointer::WlPointer::inert(inert_backend()));
+        pointer.focus = Some(2);
+        pointer
+    }
+
+    fn axis_of(frames: Vec<(usize, Vec<PointerEvent>)>) -> AxisFrame {
+        match frames.as_slice() {
+            [(2, events)] => match events.as_slice() {
+                [PointerEvent::Axis(axis)] => *axis,
+                other => panic!("expected one axis event, got {other:?}"),
+            },
+            other => panic!("expected one frame for window 2, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn value120_adds_up_within_a_frame() {
+        let mut pointer = inert_pointer();
+        // a high-resolution wheel sends fractions of a detent
+        for value120 in [30, 30, 60] {
+            pointer.axis_frame(None).axis(VERTICAL).unwrap().value120 += value120;
+        }
+        pointer.axis_frame(None).axis(HORIZONTAL).unwrap().value120 -= 40;
+
+        let axis = axis_of(pointer.take_frame());
+        assert_eq!(axis.vertical.value120, 120);
+        assert_eq!(axis.horizontal.value120, -40);
+    }
+
+    #[test]
+    fn value120_starts_over_each_frame() {
+        let mut pointer = inert_pointer();
+        pointer.axis_frame(None).axis(VERTICAL).unwrap().value120 += 120;
+        assert_eq!(axis_of(pointer.take_frame()).vertical.value120, 120);
+
+        pointer.axis_frame(None).axis(VERTICAL).unwrap().value120 += 60;
+        assert_eq!(axis_of(pointer.take_frame()).vertical.value120, 60);
+    }
+
+    #[test]
+    fn frame_without_scroll_has_no_axis_event() {
+        let mut pointer = inert_pointer();
+        pointer.axis_frame(Some(5)).source = Some(wl_pointer::AxisSource::Wheel);
+        assert!(pointer.take_frame().is_empty());
+    }
+
+    #[test]
+    fn unknown_axis_is_ignored() {
+        let mut pointer = inert_pointer();
+        assert!(pointer.axis_frame(None).axis(WEnum::Unknown(7)).is_none());
+    }
+}
@@ -0,0 +1,104 @@
+use super::data_device::{DataRead, DataWrite, send_data};
+use std::collections::HashMap;
+use std::io;
+use std::os::fd::OwnedFd;
+use wayland_protocols::wp::primary_selection::zv1::client::{
+    zwp_primary_selection_device_v1, zwp_primary_selection_offer_v1,
+    zwp_primary_selection_source_v1,
+};
+
+/// Primary selection contents offered by a client, with the MIME types they can be read as.
+#[derive(Debug)]
+pub struct PrimarySelectionOffer {
+    pub zwp_primary_selection_offer: zwp_primary_selection_offer_v1::ZwpPrimarySelectionOfferV1,
+    pub mime_types: Vec<String>,
+}
+
+impl PrimarySelectionOffer {
+    pub fn new(
+        zwp_primary_selection_offer: zwp_primary_selection_offer_v1::ZwpPrimarySelectionOfferV1,
+    ) -> Self {
+        PrimarySelectionOffer {
+            zwp_primary_selection_offer,
+            mime_types: Vec::new(),
+        }
+    }
+
+    pub fn receive(&self, mime_type: &str) -> io::Result<DataRead> {
+        DataRead::new(mime_type, |fd| {
+            self.zwp_primary_selection_offer
+                .receive(mime_type.to_string(), fd)
+        })
+    }
+
+    pub fn destroy(&self) {
+        self.zwp_primary_selection_offer.destroy();
+    }
+}
+
+/// Text selected in this client, kept until another client selects something.
+#[derive(Debug)]
+pub struct PrimarySelectionSource {
+    pub zwp_primary_selection_source: zwp_primary_selection_source_v1::ZwpPrimarySelectionSourceV1,
+    /// Contents for every offered MIME type.
+    pub data: HashMap<String, Vec<u8>>,
+}
+
+impl PrimarySelectionSource {
+    pub fn send(&self, mime_type: &str, fd: OwnedFd) -> Option<DataWrite> {
+        send_data(&self.data, mime_type, fd)
+    }
+}
+
+/// Primary selection of one seat.
+#[derive(Debug)]
+pub struct PrimarySelectionDevice {
+    pub zwp_primary_selection_device: zwp_primary_selection_device_v1::ZwpPrimarySelectionDeviceV1,
+
+    /// Offers introduced by `data_offer` that weren't made the selection yet.
+    pub offers: Vec<PrimarySelectionOffer>,
+    /// Current selection, `None` when nothing is selected.
+    pub selection: Option<PrimarySelectionOffer>,
+}
+
+impl PrimarySelectionDevice {
+    pub fn new(
+        zwp_primary_selection_device: zwp_primary_selection_device_v1::ZwpPrimarySelectionDeviceV1,
+    ) -> Self {
+        PrimarySelectionDevice {
+            zwp_primary_selection_device,
+            offers: Vec::new(),
+            selection: None,
+        }
+    }
+
+    /// Removes a pending offer to make it the selection.
+    pub fn take_offer(
+        &mut self,
+        offer: &zwp_primary_selection_offer_v1::ZwpPrimarySelectionOfferV1,
+    ) -> Option<PrimarySelectionOffer> {
+        let pos = self
+            .offers
+            .iter()
+            .position(|other| other.zwp_primary_selection_offer == *offer)?;
+        Some(self.offers.remove(pos))
+    }
+
+    /// Offer with the given protocol object, pending or current.
+    pub fn offer_mut(
+        &mut self,
+        offer: &zwp_primary_selection_offer_v1::ZwpPrimarySelectionOfferV1,
+    ) -> Option<&mut PrimarySelectionOffer> {
+        self.offers
+            .iter_mut()
+            .chain(self.selection.as_mut())
+            .find(|other| other.zwp_primary_selection_offer == *offer)
+    }
+
+    pub fn destroy(&self) {
+        for offer in self.offers.iter().chain(self.selection.as_ref()) {
+            offer.destroy();
+        }
+        self.zwp_primary_selection_device.destroy();
+    }
+}
@@ -0,0 +1,149 @@
+use super::data_device::DataDevice;
+use super::keyboard::Keyboard;
+use super::pointer::Pointer;
+use super::primary_selection::PrimarySelectionDevice;
+use super::tablet::TabletSeat;
+use super::touch::Touch;
+use wayland_client::protocol::wl_seat;
+
+/// An input seat. `id` is the registry name of the `wl_seat` global.
+#[derive(Debug)]
+pub struct Seat {
+    pub id: u32,
+    pub wl_seat: wl_seat::WlSeat,
+    pub name: Option<String>,
+    pub capabilities: wl_seat::Capability,
+
+    pub keyboard: Option<Keyboard>,
+    pub pointer: Option<Pointer>,
+    pub touch: Option<Touch>,
+    /// Tablets, tools and pads, when the compositor supports `zwp_tablet_manager_v2`.
+    pub tablet: Option<TabletSeat>,
+    /// Clipboard, when the compositor supports `wl_data_device_manager`.
+    pub data_device: Option<DataDevice>,
+    /// Primary selection, when the compositor supports `zwp_primary_selection_device_manager_v1`.
+    pub primary_selection: Option<PrimarySelectionDevice>,
+
+    /// Serial of the key or button press that arrived last. Serials wrap around, so comparing
+    /// them doesn't tell which is newer.
+    pub press_serial: Option<u32>,
+}
+
+impl Seat {
+    pub fn new(id: u32, wl_seat: wl_seat::WlSeat) -> Self {
+        Seat {
+            id,
+            wl_seat,
+            name: None,
+            capabilities: wl_seat::Capability::empty(),
+            keyboard: None,
+            pointer: None,
+            touch: None,
+            tablet: None,
+            data_device: None,
+            primary_selection: None,
+            press_serial: None,
+        }
+    }
+
+    /// Serial of the latest key or button press, needed to set the clipboard.
+    pub fn input_serial(&self) -> u32 {
+        self.press_serial.unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::client::State;
+    use super::super::testing::{inert_backend, inert_connection};
+    use super::*;
+    use wayland_client::protocol::{wl_keyboard, wl_pointer, wl_surface};
+    use wayland_client::{Dispatch, Proxy, WEnum};
+
+    fn key(state: &mut State, serial: u32, key_state: wl_keyboard::KeyState) {
+        let connection = inert_connection();
+        let keyboard = state.seats[0]
+            .keyboard
+            .as_ref()
+            .unwrap()
+            .wl_keyboard
+            .clone();
+        let event = wl_keyboard::Event::Key {
+            serial,
+            time: 0,
+            key: 30,
+            state: WEnum::Value(key_state),
+        };
+        let qhandle = connection.new_event_queue().handle();
+        State::event(state, &keyboard, event, &1, &connection, &qhandle);
+    }
+
+    fn button(state: &mut State, serial: u32, button_state: wl_pointer::ButtonState) {
+        let connection = inert_connection();
+        let pointer = state.seats[0].pointer.as_ref().unwrap().wl_pointer.clone();
+        let event = wl_pointer::Event::Button {
+            serial,
+            time: 0,
+            button: 0x110,
+            state: WEnum::Value(button_state),
+        };
+        let qhandle = connection.new_event_queue().handle();
+        State::event(state, &pointer, event, &1, &connection, &qhandle);
+    }
+
+    fn seat_state() -> State {
+        let mut seat = Seat::new(1, wl_seat::WlSeat::inert(inert_backend()));
+        seat.keyboard = Some(Keyboard::new(wl_keyboard::WlKeyboard::inert(
+            inert_backend(),
+        )));
+        let mut pointer = Pointer::new(wl_pointer::WlPointer::inert(inert_backend()));
+        pointer.focus = Some(0);
+        seat.pointer = Some(pointer);
+
+        let mut state = State::new();
+        state.seats.push(seat);
+        state
+    }
+
+    #[test]
+    fn input_serial_is_the_last_press_to_arrive() {
+        let mut state = seat_state();
+        assert_eq!(state.seats[0].input_serial(), 0);
+
+        key(&mut state, u32::MAX, wl_keyboard::KeyState::Pressed);
+        assert_eq!(state.seats[0].input_serial(), u32::MAX);
+
+        // the serial wrapped, the press is still the newer one
+        button(&mut state, 2, wl_pointer::ButtonState::Pressed);
+        assert_eq!(state.seats[0].input_serial(), 2);
+    }
+
+    #[test]
+    fn releases_and_focus_changes_are_not_presses() {
+        let mut state = seat_state();
+        button(&mut state, 5, wl_pointer::ButtonState::Pressed);
+        button(&mut state, 6, wl_pointer::ButtonState::Released);
+        key(&mut state, 7, wl_keyboard::KeyState::Released);
+
+        let connection = inert_connection();
+        let keyboard = state.seats[0]
+            .keyboard
+            .as_ref()
+            .unwrap()
+            .wl_keyboard
+            .clone();
+        let surface = wl_surface::WlSurface::inert(inert_backend());
+        let qhandle = connection.new_event_queue().handle();
+        State::event(
+            &mut state,
+            &keyboard,
+            wl_keyboard::Event::Leave { serial: 8, surface },
+            &1,
+            &connection,
+            &qhandle,
+        );
+
+        assert_eq!(state.seats[0].keyboard.as_ref().unwrap().serial, 8);
+        assert_eq!(state.seats[0].input_serial(), 5);
+    }
+}
@@ -0,0 +1,58 @@
+use std::fs::File;
+use std::io;
+use std::ptr::NonNull;
+
+/// Shared, writable mapping of a pool file. `Window::draw` hands out slices of it, so drawing
+/// writes straight into the memory the compositor reads.
+#[derive(Debug)]
+pub struct ShmMapping {
+    ptr: NonNull<u8>,
+    len: usize,
+}
+
+// SAFETY: the mapping is owned by a single `ShmMapping` and only reached through `&mut self`.
+unsafe impl Send for ShmMapping {}
+
+impl ShmMapping {
+    pub fn new(file: &File, len: usize) -> io::Result<Self> {
+        // SAFETY: a fresh shared mapping at an address picked by the kernel aliases no Rust memory
+        let ptr = unsafe {
+            rustix::mm::mmap(
+                std::ptr::null_mut(),
+                len,
+                rustix::mm::ProtFlags::READ | rustix::mm::ProtFlags::WRITE,
+                rustix::mm::MapFlags::SHARED,
+                file,
+                0,
+            )?
+        };
+        let ptr = NonNull::new(ptr.cast()).ok_or(io::ErrorKind::InvalidData)?;
+        Ok(ShmMapping { ptr, len })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Bytes `offset..offset + len` of the pool, `None` when they fall outside the mapping.
+    pub fn slice_mut(&mut self, offset: usize, len: usize) -> Option<&mut [u8]> {
+        if offset.checked_add(len)? > self.len {
+            return None;
+        }
+        // SAFETY: the range is inside the mapping, and `&mut self` keeps it from being aliased
+        Some(unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr().add(offset), len) })
+    }
+}
+
+impl Drop for ShmMapping {
+    fn drop(&mut self) {
+        // SAFETY: `ptr` and `len` describe the mapping created in `new`, nothing borrows it anymore
+        unsafe {
+            let _ = rustix::mm::munmap(self.ptr.as_ptr().cast(), self.len);
+        }
+    }
+}
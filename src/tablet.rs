@@ -0,0 +1,225 @@
+use wayland_client::Proxy;
+use wayland_protocols::wp::tablet::zv2::client::{
+    zwp_tablet_pad_group_v2, zwp_tablet_pad_v2, zwp_tablet_seat_v2, zwp_tablet_tool_v2,
+    zwp_tablet_v2,
+};
+
+/// Full range of the pressure, distance and slider axes in the protocol.
+const AXIS_MAX: f64 = 65535.0;
+
+/// A graphics tablet. `id` is the protocol id of its `zwp_tablet_v2`, tools and pads refer to it.
+#[derive(Debug)]
+pub struct Tablet {
+    pub id: u32,
+    pub zwp_tablet: zwp_tablet_v2::ZwpTabletV2,
+
+    pub name: Option<String>,
+    /// USB vendor and product id.
+    pub vid: Option<u32>,
+    pub pid: Option<u32>,
+    /// Device paths, e.g. under /dev/input.
+    pub paths: Vec<String>,
+
+    /// Set once `zwp_tablet_v2.done` arrived.
+    pub ready: bool,
+}
+
+impl Tablet {
+    pub fn new(zwp_tablet: zwp_tablet_v2::ZwpTabletV2) -> Self {
+        Tablet {
+            id: zwp_tablet.id().protocol_id(),
+            zwp_tablet,
+            name: None,
+            vid: None,
+            pid: None,
+            paths: Vec::new(),
+            ready: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TabletToolEvent {
+    /// The tool came close to `tablet` over the window.
+    ProximityIn {
+        tablet: u32,
+        serial: u32,
+    },
+    ProximityOut,
+    /// The tip touches the tablet.
+    Down {
+        serial: u32,
+    },
+    Up,
+    /// Positions are in logical surface coordinates, `buffer_position` is the same point in
+    /// buffer pixels.
+    Motion {
+        position: (f64, f64),
+        buffer_position: (f64, f64),
+    },
+    /// From 0.0 to 1.0.
+    Pressure(f64),
+    /// From 0.0 to 1.0, 0.0 being closest to the tablet.
+    Distance(f64),
+    /// Degrees the tool is tilted along each axis, positive towards the right and the bottom.
+    Tilt {
+        x: f64,
+        y: f64,
+    },
+    /// Degrees clockwise from the tool's logical neutral position.
+    Rotation(f64),
+    /// Airbrush finger wheel, from -1.0 to 1.0.
+    Slider(f64),
+    Wheel {
+        degrees: f64,
+        clicks: i32,
+    },
+    /// `button` is an evdev code such as `BTN_STYLUS`.
+    Button {
+        button: u32,
+        pressed: bool,
+        serial: u32,
+    },
+}
+
+impl TabletToolEvent {
+    pub fn pressure(raw: u32) -> Self {
+        TabletToolEvent::Pressure(raw as f64 / AXIS_MAX)
+    }
+
+    pub fn distance(raw: u32) -> Self {
+        TabletToolEvent::Distance(raw as f64 / AXIS_MAX)
+    }
+
+    pub fn slider(raw: i32) -> Self {
+        TabletToolEvent::Slider(raw as f64 / AXIS_MAX)
+    }
+}
+
+/// A pen, eraser, airbrush or other tool, known to the compositor once it was first used. `id` is
+/// the protocol id of its `zwp_tablet_tool_v2`.
+#[derive(Debug)]
+pub struct TabletTool {
+    pub id: u32,
+    pub zwp_tablet_tool: zwp_tablet_tool_v2::ZwpTabletToolV2,
+
+    pub tool_type: Option<zwp_tablet_tool_v2::Type>,
+    pub hardware_serial: Option<u64>,
+    pub hardware_id: Option<u64>,
+    pub capabilities: Vec<zwp_tablet_tool_v2::Capability>,
+    /// Set once `zwp_tablet_tool_v2.done` arrived.
+    pub ready: bool,
+
+    /// Window the tool is in proximity of.
+    pub focus: Option<usize>,
+    /// Events received since the last `zwp_tablet_tool_v2.frame`.
+    pub pending: Vec<TabletToolEvent>,
+}
+
+impl TabletTool {
+    pub fn new(zwp_tablet_tool: zwp_tablet_tool_v2::ZwpTabletToolV2) -> Self {
+        TabletTool {
+            id: zwp_tablet_tool.id().protocol_id(),
+            zwp_tablet_tool,
+            tool_type: None,
+            hardware_serial: None,
+            hardware_id: None,
+            capabilities: Vec::new(),
+            ready: false,
+            focus: None,
+            pending: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TabletPadEvent {
+    /// The pad of `tablet` is now directed at the window.
+    Enter {
+        tablet: u32,
+        serial: u32,
+    },
+    Leave {
+        serial: u32,
+    },
+    /// `button` is the index of the button on the pad, starting at 0.
+    Button {
+        button: u32,
+        pressed: bool,
+        time: u32,
+    },
+}
+
+/// The buttons (and rings and strips) next to the drawing area of a tablet. `id` is the protocol
+/// id of its `zwp_tablet_pad_v2`.
+#[derive(Debug)]
+pub struct TabletPad {
+    pub id: u32,
+    pub zwp_tablet_pad: zwp_tablet_pad_v2::ZwpTabletPadV2,
+    /// Button groups, kept to destroy them along with the pad.
+    pub groups: Vec<zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2>,
+
+    pub paths: Vec<String>,
+    pub buttons: u32,
+    /// Set once `zwp_tablet_pad_v2.done` arrived.
+    pub ready: bool,
+
+    /// Window the pad events go to.
+    pub focus: Option<usize>,
+}
+
+impl TabletPad {
+    pub fn new(zwp_tablet_pad: zwp_tablet_pad_v2::ZwpTabletPadV2) -> Self {
+        TabletPad {
+            id: zwp_tablet_pad.id().protocol_id(),
+            zwp_tablet_pad,
+            groups: Vec::new(),
+            paths: Vec::new(),
+            buttons: 0,
+            ready: false,
+            focus: None,
+        }
+    }
+
+    pub fn destroy(&self) {
+        for group in &self.groups {
+            group.destroy();
+        }
+        self.zwp_tablet_pad.destroy();
+    }
+}
+
+/// Tablet devices of one seat.
+#[derive(Debug)]
+pub struct TabletSeat {
+    pub zwp_tablet_seat: zwp_tablet_seat_v2::ZwpTabletSeatV2,
+
+    pub tablets: Vec<Tablet>,
+    pub tools: Vec<TabletTool>,
+    pub pads: Vec<TabletPad>,
+}
+
+impl TabletSeat {
+    pub fn new(zwp_tablet_seat: zwp_tablet_seat_v2::ZwpTabletSeatV2) -> Self {
+        TabletSeat {
+            zwp_tablet_seat,
+            tablets: Vec::new(),
+            tools: Vec::new(),
+            pads: Vec::new(),
+        }
+    }
+
+    /// Destroys the tablet seat along with every device it announced.
+    pub fn destroy(&self) {
+        for tablet in &self.tablets {
+            tablet.zwp_tablet.destroy();
+        }
+        for tool in &self.tools {
+            tool.zwp_tablet_tool.destroy();
+        }
+        for pad in &self.pads {
+            pad.destroy();
+        }
+        self.zwp_tablet_seat.destroy();
+    }
+}
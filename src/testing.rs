@@ -0,0 +1,15 @@
+use std::os::unix::net::UnixStream;
+use wayland_client::Connection;
+use wayland_client::backend::WeakBackend;
+
+/// Connection without a compositor on the other end. Proxies made with `inert` drop every
+/// request, so client-side bookkeeping runs as usual while nothing is sent.
+pub(crate) fn inert_connection() -> Connection {
+    let (socket, _peer) = UnixStream::pair().unwrap();
+    Connection::from_socket(socket).unwrap()
+}
+
+/// Backend for inert proxies, as in `WlPointer::inert(inert_backend())`.
+pub(crate) fn inert_backend() -> WeakBackend {
+    inert_connection().backend().downgrade()
+}
@@ -0,0 +1,187 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+use wayland_protocols::wp::presentation_time::client::wp_presentation_feedback;
+
+/// Number of frame intervals kept for the rolling statistics.
+const FRAME_WINDOW: usize = 120;
+
+/// An interval this much longer than the shortest recent one counts as missed frames.
+const MISSED_FRAME_FACTOR: f64 = 1.5;
+
+/// Rolling statistics built from `wl_callback.done` timestamps.
+#[derive(Debug, Default, Clone)]
+pub struct FrameStats {
+    /// Timestamp of the last frame callback, in milliseconds with an undefined base.
+    pub last_time: Option<u32>,
+    /// Most recent intervals between frame callbacks in milliseconds, newest last.
+    pub intervals: VecDeque<u32>,
+
+    pub frames: u64,
+    pub missed_frames: u64,
+}
+
+impl FrameStats {
+    /// Records a frame callback and returns the interval since the previous one.
+    pub fn record(&mut self, time: u32) -> Option<u32> {
+        self.frames += 1;
+
+        let interval = self.last_time.map(|last| time.wrapping_sub(last));
+        self.last_time = Some(time);
+
+        if let Some(interval) = interval {
+            // a refresh cycle is approximated by the fastest interval seen recently
+            if let Some(&shortest) = self.intervals.iter().filter(|&&i| i > 0).min() {
+                let ratio = interval as f64 / shortest as f64;
+                if ratio >= MISSED_FRAME_FACTOR {
+                    self.missed_frames += ratio.round() as u64 - 1;
+                }
+            }
+
+            self.intervals.push_back(interval);
+            if self.intervals.len() > FRAME_WINDOW {
+                self.intervals.pop_front();
+            }
+        }
+
+        interval
+    }
+
+    pub fn average_interval(&self) -> Option<Duration> {
+        if self.intervals.is_empty() {
+            return None;
+        }
+
+        let total: u64 = self.intervals.iter().map(|&i| i as u64).sum();
+        Some(Duration::from_secs_f64(
+            total as f64 / self.intervals.len() as f64 / 1000.0,
+        ))
+    }
+
+    /// Frames per second over the rolling window.
+    pub fn fps(&self) -> Option<f64> {
+        match self.average_interval() {
+            Some(interval) if !interval.is_zero() => Some(1.0 / interval.as_secs_f64()),
+            _ => None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = FrameStats::default();
+    }
+}
+
+/// When and how a committed frame reached the screen, as reported by `wp_presentation_feedback`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PresentationInfo {
+    /// Presentation timestamp in the clock announced by `Client::presentation_clock`.
+    pub time: Duration,
+    /// Duration of one refresh cycle, zero if the output has no constant refresh rate.
+    pub refresh: Duration,
+    /// Vertical retrace counter of the output, 0 when not supported.
+    pub sequence: u64,
+    pub flags: wp_presentation_feedback::Kind,
+}
+
+impl PresentationInfo {
+    /// Joins the split 64-bit fields of `wp_presentation_feedback.presented`: `time` is
+    /// `(tv_sec_hi, tv_sec_lo, tv_nsec)`, `sequence` is `(seq_hi, seq_lo)`, `refresh` is in
+    /// nanoseconds.
+    pub fn from_feedback(
+        time: (u32, u32, u32),
+        refresh: u32,
+        sequence: (u32, u32),
+        flags: wp_presentation_feedback::Kind,
+    ) -> Self {
+        let (tv_sec_hi, tv_sec_lo, tv_nsec) = time;
+        let (seq_hi, seq_lo) = sequence;
+        PresentationInfo {
+            time: Duration::new(((tv_sec_hi as u64) << 32) | tv_sec_lo as u64, tv_nsec),
+            refresh: Duration::from_nanos(refresh as u64),
+            sequence: ((seq_hi as u64) << 32) | seq_lo as u64,
+            flags,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_callback_has_no_interval() {
+        let mut stats = FrameStats::default();
+        assert_eq!(stats.record(1000), None);
+        assert_eq!(stats.frames, 1);
+        assert_eq!(stats.average_interval(), None);
+        assert_eq!(stats.fps(), None);
+    }
+
+    #[test]
+    fn intervals_average_into_fps() {
+        let mut stats = FrameStats::default();
+        for time in [0, 20, 32, 48] {
+            stats.record(time);
+        }
+        assert_eq!(stats.intervals, [20, 12, 16]);
+        assert_eq!(stats.average_interval(), Some(Duration::from_millis(16)));
+        assert!((stats.fps().unwrap() - 62.5).abs() < 1e-9);
+        assert_eq!(stats.missed_frames, 0);
+    }
+
+    #[test]
+    fn timestamps_wrap_around() {
+        let mut stats = FrameStats::default();
+        stats.record(u32::MAX - 5);
+        assert_eq!(stats.record(10), Some(16));
+    }
+
+    #[test]
+    fn long_intervals_count_missed_frames() {
+        let mut stats = FrameStats::default();
+        for time in [0, 16, 32, 80] {
+            stats.record(time);
+        }
+        // 48ms is three refresh cycles of 16ms, two of them without a frame
+        assert_eq!(stats.missed_frames, 2);
+    }
+
+    #[test]
+    fn window_keeps_the_latest_intervals() {
+        let mut stats = FrameStats::default();
+        for frame in 0..=FRAME_WINDOW as u32 + 10 {
+            stats.record(frame * 16);
+        }
+        assert_eq!(stats.intervals.len(), FRAME_WINDOW);
+
+        stats.reset();
+        assert_eq!(stats.frames, 0);
+        assert!(stats.intervals.is_empty());
+    }
+
+    #[test]
+    fn presentation_feedback_joins_split_fields() {
+        let info = PresentationInfo::from_feedback(
+            (1, 2, 500_000_000),
+            16_666_666,
+            (3, 4),
+            wp_presentation_feedback::Kind::Vsync,
+        );
+        assert_eq!(info.time, Duration::new((1 << 32) + 2, 500_000_000));
+        assert_eq!(info.refresh, Duration::from_nanos(16_666_666));
+        assert_eq!(info.sequence, (3 << 32) + 4);
+        assert_eq!(info.flags, wp_presentation_feedback::Kind::Vsync);
+    }
+
+    #[test]
+    fn presentation_feedback_without_refresh_or_sequence() {
+        let info = PresentationInfo::from_feedback(
+            (0, 7, 0),
+            0,
+            (0, 0),
+            wp_presentation_feedback::Kind::empty(),
+        );
+        assert_eq!(info.time, Duration::from_secs(7));
+        assert!(info.refresh.is_zero());
+        assert_eq!(info.sequence, 0);
+    }
+}
@@ -0,0 +1,142 @@
+use wayland_client::protocol::wl_touch;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TouchEvent {
+    /// A new touch point `id` appeared. Positions are in logical surface coordinates,
+    /// `buffer_position` is the same point in buffer pixels.
+    Down {
+        id: i32,
+        position: (f64, f64),
+        buffer_position: (f64, f64),
+        serial: u32,
+        time: u32,
+    },
+    /// Touch point `id` was lifted, the id may be reused by a later `Down`.
+    Up { id: i32, serial: u32, time: u32 },
+    Motion {
+        id: i32,
+        position: (f64, f64),
+        buffer_position: (f64, f64),
+        time: u32,
+    },
+    /// The compositor took over every touch point of the window, e.g. for a gesture. No `Up`
+    /// follows for them.
+    Cancel,
+    /// Size of the contact area as an ellipse, major and minor axis in surface coordinates.
+    Shape { id: i32, major: f64, minor: f64 },
+    /// Angle of the major axis of the contact ellipse, in degrees clockwise from the surface
+    /// y axis.
+    Orientation { id: i32, orientation: f64 },
+}
+
+/// A finger currently down, with the window it went down on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TouchPoint {
+    pub id: i32,
+    pub window: usize,
+    pub position: (f64, f64),
+}
+
+#[derive(Debug)]
+pub struct Touch {
+    pub wl_touch: wl_touch::WlTouch,
+
+    /// Touch points between their down and up.
+    pub points: Vec<TouchPoint>,
+    /// Serial of the last down, needed for moves and drags started by touch.
+    pub down_serial: u32,
+
+    /// Events received since the last `wl_touch.frame`, with the window they belong to.
+    pub pending: Vec<(usize, TouchEvent)>,
+}
+
+impl Touch {
+    pub fn new(wl_touch: wl_touch::WlTouch) -> Self {
+        Touch {
+            wl_touch,
+            points: Vec::new(),
+            down_serial: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn point(&self, id: i32) -> Option<&TouchPoint> {
+        self.points.iter().find(|point| point.id == id)
+    }
+
+    pub fn point_mut(&mut self, id: i32) -> Option<&mut TouchPoint> {
+        self.points.iter_mut().find(|point| point.id == id)
+    }
+
+    /// Drops every touch point, queueing one `Cancel` for each window that had some.
+    pub fn cancel(&mut self) {
+        let mut windows: Vec<usize> = self.points.drain(..).map(|point| point.window).collect();
+        windows.sort_unstable();
+        windows.dedup();
+        for window in windows {
+            self.pending.push((window, TouchEvent::Cancel));
+        }
+    }
+
+    /// Ends the current frame, returning its events grouped by window in order.
+    pub fn take_frame(&mut self) -> Vec<(usize, Vec<TouchEvent>)> {
+        let mut frames: Vec<(usize, Vec<TouchEvent>)> = Vec::new();
+        for (window, event) in self.pending.drain(..) {
+            match frames.last_mut() {
+                Some((last, events)) if *last == window => events.push(event),
+                _ => frames.push((window, vec![event])),
+            }
+        }
+        frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::inert_backend;
+    use super::*;
+    use wayland_client::Proxy;
+
+    fn point(id: i32, window: usize) -> TouchPoint {
+        TouchPoint {
+            id,
+            window,
+            position: (0.0, 0.0),
+        }
+    }
+
+    #[test]
+    fn cancel_drops_every_point_once_per_window() {
+        let mut touch = Touch::new(wl_touch::WlTouch::inert(inert_backend()));
+        touch.points = vec![point(0, 1), point(1, 0), point(2, 1)];
+
+        touch.cancel();
+        assert!(touch.points.is_empty());
+        assert!(touch.point(0).is_none());
+        assert_eq!(
+            touch.take_frame(),
+            vec![(0, vec![TouchEvent::Cancel]), (1, vec![TouchEvent::Cancel])]
+        );
+
+        // nothing left to cancel
+        touch.cancel();
+        assert!(touch.take_frame().is_empty());
+    }
+
+    #[test]
+    fn frame_groups_consecutive_events_by_window() {
+        let mut touch = Touch::new(wl_touch::WlTouch::inert(inert_backend()));
+        let up = |id| TouchEvent::Up {
+            id,
+            serial: 0,
+            time: 0,
+        };
+        touch.pending = vec![(0, up(0)), (0, up(1)), (1, up(2)), (0, up(3))];
+
+        assert_eq!(
+            touch.take_frame(),
+            vec![(0, vec![up(0), up(1)]), (1, vec![up(2)]), (0, vec![up(3)])]
+        );
+        assert!(touch.pending.is_empty());
+    }
+}